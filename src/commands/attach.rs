@@ -0,0 +1,39 @@
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use std::{fs, path::Path};
+
+use super::edit::sync_tag;
+use crate::{git, notes::Resolver, opt::Opt, taskwarrior::Task};
+
+/// Copies files into the directory of a task. The first argument selects the
+/// task, and every remaining argument is a file to attach.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let (filter, files) = match opt.args.split_first() {
+        Some((filter, files)) if !files.is_empty() => (filter, files),
+        _ => return Err(anyhow!("usage: taskn attach <id> <files...>")),
+    };
+
+    let task = Task::get_single(filter)?;
+    let mut resolver = Resolver::new(opt)?;
+    let dir = resolver
+        .prepare_dir(&task)?
+        .ok_or_else(|| anyhow!("attachments require the `dir` layout (--layout dir)"))?;
+    let note = resolver.note_path(&task);
+
+    for file in files {
+        let source = Path::new(file);
+        let name = source
+            .file_name()
+            .ok_or_else(|| anyhow!("'{}' is not a file", file))?;
+        let destination = dir.join(name);
+        if destination == note {
+            return Err(anyhow!("'{}' would overwrite the note of the task", file));
+        }
+        fs::copy(source, &destination)?;
+        println!("{} {}", "attached".green(), destination.display());
+    }
+
+    sync_tag(&resolver, &task)?;
+    git::record(opt, "Attach files to", &[task])?;
+    Ok(())
+}
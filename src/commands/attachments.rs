@@ -0,0 +1,26 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{notes::Resolver, opt::Opt, taskwarrior::Task};
+
+/// Lists the attachments of every task selected by the arguments.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let tasks = Task::get(opt.args.iter())?;
+    let multiple = tasks.len() > 1;
+    let resolver = Resolver::new(opt)?;
+
+    for task in tasks {
+        let attachments = resolver.attachments(&task)?;
+        if multiple {
+            if attachments.is_empty() {
+                continue;
+            }
+            println!("{} {}", task.id.to_string().bold(), task.description.bold());
+        }
+        for attachment in attachments {
+            println!("{}", attachment.display());
+        }
+    }
+
+    Ok(())
+}
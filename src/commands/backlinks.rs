@@ -0,0 +1,45 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{
+    links,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskwarrior::Task,
+};
+
+/// Lists every note which links to the tasks selected by the arguments.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let targets = Task::get(opt.args.iter())?;
+    let all_tasks = Task::get(std::iter::empty::<&str>())?;
+    let backlinks = links::backlinks(&Resolver::new(opt)?)?;
+
+    for target in targets {
+        println!("{} {}", describe(&target).bold(), target.uuid.dimmed());
+        match backlinks.get(&target.uuid) {
+            None => println!("  {}", "no backlinks".dimmed()),
+            Some(sources) =>
+                for source in sources {
+                    match all_tasks
+                        .iter()
+                        .find(|task| notes::key_matches(source, &task.uuid))
+                    {
+                        Some(task) => println!("  {}", describe(task)),
+                        None => println!("  {} {}", source, "(unknown task)".red()),
+                    }
+                },
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats a task as its ID (or status, for tasks without one) and
+/// description.
+pub(crate) fn describe(task: &Task) -> String {
+    if task.id == 0 {
+        format!("[{}] {}", task.status, task.description)
+    } else {
+        format!("{} {}", task.id, task.description)
+    }
+}
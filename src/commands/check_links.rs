@@ -0,0 +1,46 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use super::backlinks::describe;
+use crate::{
+    links,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskwarrior::Task,
+};
+
+/// Checks the links inside of every note, reporting links which point to
+/// deleted tasks or to tasks which do not exist.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let all_tasks = Task::get(std::iter::empty::<&str>())?;
+
+    let mut broken_count = 0;
+    let resolver = Resolver::new(opt)?;
+    for (key, path) in resolver.notes() {
+        let contents = notes::read(opt, path)?;
+        let broken = links::broken(&contents, &all_tasks);
+        if broken.is_empty() {
+            continue;
+        }
+
+        let source = all_tasks
+            .iter()
+            .find(|task| notes::key_matches(key, &task.uuid))
+            .map_or_else(|| key.to_string(), describe);
+        println!("{}", source.bold());
+        for target in broken {
+            let reason = if all_tasks.iter().any(|task| task.uuid == target) {
+                "deleted"
+            } else {
+                "missing"
+            };
+            println!("  {} {}", target, format!("({})", reason).red());
+            broken_count += 1;
+        }
+    }
+
+    if broken_count == 0 {
+        println!("{}", "all links are intact".green());
+    }
+    Ok(())
+}
@@ -1,91 +1,158 @@
 use std::{
-    fs::{create_dir_all, File},
-    io::{self, BufRead, BufReader},
+    io,
     path::PathBuf,
-    process::{exit, Command},
+    process::Command,
 };
 
-use anyhow::Result;
-use crate::{opt::Opt, taskwarrior::Task};
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+use crate::{
+    crypt::Scratch,
+    git, links,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskn_warning,
+    taskwarrior::Task,
+};
 
 pub(crate) fn execute(opt: &Opt) -> Result<()> {
     let tasks = Task::get(opt.args.iter())?;
+    edit(opt, &tasks)
+}
 
-    if create_dir_all(&opt.root_dir).is_err() {
-        eprintln!("Failed to create taskn directory '{}'", &opt.root_dir);
-        exit(1)
+/// Opens the notes of the given tasks in the editor. Afterwards, links inside
+/// of the notes are rewritten and the `taskn` tag is synchronized.
+pub(crate) fn edit(opt: &Opt, tasks: &[Task]) -> Result<()> {
+    let mut resolver = Resolver::new(opt)?;
+
+    for task in tasks {
+        // readable names follow the task's description and project
+        resolver.rename(task)?;
+        // with `--encrypt`, this also encrypts a note which is still plaintext
+        resolver
+            .prepare(task)
+            .with_context(|| format!("failed to prepare the note of task {}", task.id))?;
     }
 
+    // encrypted notes are decrypted into a private scratch directory while they
+    // are being edited
+    let scratch = if opt.encrypt {
+        let notes = tasks
+            .iter()
+            .map(|task| (task.uuid.clone(), resolver.note_path(task)))
+            .collect::<Vec<_>>();
+        Some(Scratch::open(opt, &notes)?)
+    } else {
+        None
+    };
+
     let status = Command::new(&opt.editor)
-        .args(
-            tasks
-                .iter()
-                .map(|task| task_path(opt, task))
-                .collect::<Vec<PathBuf>>(),
-        )
+        .args(scratch.as_ref().map_or_else(
+            || {
+                tasks
+                    .iter()
+                    .map(|task| resolver.note_path(task))
+                    .collect::<Vec<PathBuf>>()
+            },
+            Scratch::paths,
+        ))
         .status()?;
 
     if !status.success() {
-        eprintln!("Failed to open editor '{}' ", &opt.editor);
-        exit(1)
+        drop(scratch);
+        return Err(anyhow!("failed to open editor '{}'", &opt.editor));
+    }
+
+    if let Some(scratch) = scratch {
+        scratch.seal(opt)?;
     }
 
+    finish(&resolver, tasks)
+}
+
+/// Catches up with changes to the notes of the given tasks: links inside of
+/// them are rewritten, the `taskn` tag is synchronized and the change is
+/// recorded in history.
+pub(crate) fn finish(resolver: &Resolver, tasks: &[Task]) -> Result<()> {
+    update_links(resolver, tasks)?;
+
     for task in tasks {
-        let has_note = task_has_note(opt, &task)?;
-        let has_tag = task.has_tag("taskn");
-
-        let action = if has_note && !has_tag {
-            Some("+taskn")
-        } else if !has_note && has_tag {
-            Some("-taskn")
-        } else {
-            None
-        };
-
-        if let Some(action) = action {
-            let status = Command::new("task")
-                .arg(&task.uuid)
-                .arg("modify")
-                .arg(action)
-                .output()?
-                .status;
-            if !status.success() {
-                eprintln!("Failed to annotate task '{}' with taskn status", task.id);
-                exit(1)
-            }
-        }
+        resolver.tidy(task)?;
+        sync_tag(resolver, task)?;
     }
 
+    git::record(resolver.opt(), "Edit", tasks)?;
+
     Ok(())
 }
 
-fn task_has_note(opt: &Opt, task: &Task) -> io::Result<bool> {
-    // a lot of editors will keep an "empty" line at the top of a file, so a naive
-    // 'byte size == 0' check won't cut it.
-    //
-    // because we expect notes to be VERY small (on the order of KB at most), we can
-    // just scan to see if there's any non-whitespace.
-    //
-    // NOTE: if perf becomes an issue, this will become a good place to refactor
-    let file = match File::open(task_path(opt, task)) {
-        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
-        Err(e) => return Err(e),
-        Ok(file) => file,
+/// Adds the `taskn` tag to a task which has a note, and removes it from a task
+/// which does not.
+pub(crate) fn sync_tag(resolver: &Resolver, task: &Task) -> Result<()> {
+    let has_note = task_has_note(resolver, task)?;
+    let has_tag = task.has_tag("taskn");
+
+    let action = if has_note && !has_tag {
+        Some("+taskn")
+    } else if !has_note && has_tag {
+        Some("-taskn")
+    } else {
+        None
     };
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        for c in line?.chars() {
-            if !c.is_whitespace() {
-                return Ok(true);
-            }
+
+    if let Some(action) = action {
+        let status = Command::new("task")
+            .arg(&task.uuid)
+            .arg("modify")
+            .arg(action)
+            .output()?
+            .status;
+        if !status.success() {
+            return Err(anyhow!(
+                "failed to annotate task '{}' with taskn status",
+                task.id
+            ));
         }
     }
-    Ok(false)
+
+    Ok(())
+}
+
+/// A task has a note if its note has any content, or if it has any
+/// attachments.
+pub(crate) fn task_has_note(resolver: &Resolver, task: &Task) -> io::Result<bool> {
+    Ok(notes::has_content(resolver.opt(), &resolver.note_path(task))?
+        || !resolver.attachments(task)?.is_empty())
 }
 
-fn task_path(opt: &Opt, task: &Task) -> PathBuf {
-    PathBuf::new()
-        .join(&opt.root_dir)
-        .join(&task.uuid)
-        .with_extension(&opt.file_format)
+/// Rewrites the `[[task:...]]` links inside of each of the notes so that they
+/// point to task UUIDs, and warns about links which are unresolvable or which
+/// point to deleted tasks.
+fn update_links(resolver: &Resolver, tasks: &[Task]) -> Result<()> {
+    let mut all_tasks = None;
+    for task in tasks {
+        let path = resolver.note_path(task);
+        let contents = notes::read(resolver.opt(), &path)?;
+        if links::parse(&contents).is_empty() {
+            continue;
+        }
+
+        // only export every task once, and only if a note actually has links
+        if all_tasks.is_none() {
+            all_tasks = Some(Task::get(std::iter::empty::<&str>())?);
+        }
+        let all_tasks = all_tasks.as_deref().unwrap_or_default();
+
+        let (rewritten, unresolved) = links::rewrite(&contents, all_tasks);
+        if rewritten != contents {
+            notes::write(resolver.opt(), &path, &rewritten)?;
+        }
+        for target in unresolved {
+            taskn_warning!("task {}: could not resolve link to '{}'", task.id, target);
+        }
+        for target in links::broken(&rewritten, all_tasks) {
+            taskn_warning!("task {}: link to deleted task '{}'", task.id, target);
+        }
+    }
+    Ok(())
 }
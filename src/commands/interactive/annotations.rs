@@ -0,0 +1,234 @@
+use anyhow::Result;
+use chrono::Local;
+use termion::event::Key;
+use tui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Span, Spans},
+    widgets::{Clear, List, ListItem, ListState},
+};
+
+use super::{
+    columns, common_render, editor, input::LineInput, preview::popup, prompt_layout,
+    render_prompt, ActionResult, CommonState, Frame, Mode, Normal,
+};
+use crate::{
+    opt::Opt,
+    taskwarrior::{Annotation, Task},
+};
+
+/// How long ago an annotation was made, e.g. `3d ago`.
+pub(crate) fn ago(annotation: &Annotation) -> String {
+    format!("{} ago", columns::age(Local::now() - annotation.entry.0))
+}
+
+/// The line an annotation becomes once it's moved into the note
+fn log_entry(annotation: &Annotation) -> String {
+    format!(
+        "- {} {}",
+        annotation.entry.0.format("%Y-%m-%d"),
+        annotation.description
+    )
+}
+
+/// Adds an annotation to the selected task.
+pub(crate) struct AnnotatePrompt {
+    task:  Task,
+    input: LineInput,
+    /// Set when the annotation could not be added
+    error: Option<String>,
+}
+
+impl AnnotatePrompt {
+    pub(crate) fn new(task: &Task) -> Self {
+        Self {
+            task:  task.clone(),
+            input: LineInput::default(),
+            error: None,
+        }
+    }
+}
+
+impl Mode for AnnotatePrompt {
+    fn name(&self) -> &'static str {
+        "ANNOTATE"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let (main, prompt) = prompt_layout(area);
+        common_render(frame, common_state, &[Modifier::DIM], main);
+        let title = match &self.error {
+            Some(error) => format!("Annotate task {} ({})", self.task.id, error),
+            None => format!("Annotate task {}", self.task.id),
+        };
+        render_prompt(frame, &common_state.theme, &title, &self.input, prompt);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Esc =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                }),
+            Key::Char('\n') => {
+                let text = self.input.text().trim();
+                if text.is_empty() {
+                    self.error = Some("an annotation is required".to_string());
+                    return Ok(ActionResult::default());
+                }
+                match self.task.annotate(text) {
+                    Ok(()) => {
+                        common_state.show_message(format!("Annotated task {}", self.task.id));
+                        return Ok(ActionResult {
+                            new_mode: Some(Box::new(Normal)),
+                            should_load: true,
+                            ..ActionResult::default()
+                        });
+                    },
+                    Err(e) => self.error = Some(e.to_string()),
+                }
+            },
+            key =>
+                if self.input.handle(key) {
+                    self.error = None;
+                },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+/// Lists the selected task's annotations, to delete them or to move them into
+/// its note as dated log entries.
+pub(crate) struct Annotations {
+    task:     Task,
+    selected: usize,
+}
+
+impl Annotations {
+    pub(crate) fn new(task: &Task) -> Self {
+        Self {
+            task:     task.clone(),
+            selected: 0,
+        }
+    }
+
+    fn annotations(&self) -> &[Annotation] {
+        self.task.annotations.as_deref().unwrap_or_default()
+    }
+
+    /// Forgets the selected annotation once it's removed from the task, going
+    /// back to the task list after the last one.
+    fn remove_selected(&mut self) -> ActionResult {
+        if let Some(annotations) = &mut self.task.annotations {
+            annotations.remove(self.selected);
+        }
+        let left = self.annotations().len();
+        self.selected = self.selected.min(left.saturating_sub(1));
+        let new_mode: Option<Box<dyn Mode>> = if left == 0 {
+            Some(Box::new(Normal))
+        } else {
+            None
+        };
+        ActionResult {
+            new_mode,
+            should_load: true,
+            ..ActionResult::default()
+        }
+    }
+}
+
+impl Mode for Annotations {
+    fn name(&self) -> &'static str {
+        "ANNOTATIONS"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        common_render(frame, common_state, &[Modifier::DIM], area);
+
+        let items = self
+            .annotations()
+            .iter()
+            .map(|annotation| {
+                ListItem::new(Spans::from(vec![
+                    Span::styled(format!("{:>10} ", ago(annotation)), common_state.theme.hint),
+                    Span::raw(annotation.description.clone()),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let mut list_state = ListState::default();
+        list_state.select(Some(self.selected).filter(|_| !items.is_empty()));
+
+        #[allow(clippy::cast_possible_truncation)]
+        let height = (items.len() + 2).min(usize::from(area.height.saturating_sub(4))) as u16;
+        let area = popup(area, height);
+        let title = format!(
+            "Annotations of task {} (d delete, ENTER move to note, ESC close)",
+            self.task.id
+        );
+        let list = List::new(items)
+            .block(common_state.theme.block().title(title))
+            .highlight_style(common_state.theme.selected)
+            .highlight_symbol("> ");
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut list_state);
+    }
+
+    fn update(
+        &mut self,
+        opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        let annotation = match self.annotations().get(self.selected) {
+            Some(annotation) => annotation.clone(),
+            None => {
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                })
+            },
+        };
+        match key {
+            Key::Char('j') | Key::Down =>
+                self.selected = (self.selected + 1).min(self.annotations().len() - 1),
+            Key::Char('k') | Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Char('d') | Key::Delete => {
+                self.task.denotate(&annotation.description)?;
+                common_state
+                    .show_message(format!("Deleted an annotation of task {}", self.task.id));
+                return Ok(self.remove_selected());
+            },
+            // the note is written first, so that a failure loses nothing
+            Key::Char('\n') => {
+                let contents = common_state.contents_of(&self.task.uuid);
+                let contents = contents.trim_end();
+                let separator = match contents.lines().last() {
+                    None => "",
+                    Some(line) if line.starts_with("- ") => "\n",
+                    Some(_) => "\n\n",
+                };
+                let text = format!("{}{}{}\n", contents, separator, log_entry(&annotation));
+                editor::save_note(opt, &self.task, &text)?;
+                self.task.denotate(&annotation.description)?;
+                common_state.show_message(format!(
+                    "Moved an annotation into the note of task {}",
+                    self.task.id
+                ));
+                return Ok(self.remove_selected());
+            },
+            Key::Esc | Key::Char('q') =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                }),
+            _ => {},
+        }
+        Ok(ActionResult::default())
+    }
+}
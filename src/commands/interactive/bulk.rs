@@ -0,0 +1,333 @@
+use anyhow::Result;
+use termion::event::Key;
+use tui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use super::{
+    common_render, default_layout, input::LineInput, prompt_layout, render_prompt, render_tasks,
+    ActionResult, CommonState, Frame, Mode, Normal,
+};
+use crate::{
+    opt::Opt,
+    taskwarrior::{self, ParsableDateTime, Task},
+};
+
+/// A change made to every marked task at once
+pub(crate) enum Action {
+    Done,
+    Delete,
+    AddTag(String),
+    RemoveTag(String),
+    /// Moves the tasks into a project, or out of theirs
+    Project(Option<String>),
+    /// Hides the tasks until a date, or shows them again
+    Wait(Option<ParsableDateTime>),
+}
+
+impl Action {
+    /// Asks whether to go ahead, e.g. `Mark 3 tasks done?`.
+    fn question(&self, tasks: &[&Task]) -> String {
+        let which = match tasks {
+            [task] => format!("task {}", task.id),
+            tasks => format!("{} tasks", tasks.len()),
+        };
+        match self {
+            Self::Done => format!("Mark {} done?", which),
+            Self::Delete => format!("Delete {}?", which),
+            Self::AddTag(tag) => format!("Tag {} with +{}?", which, tag),
+            Self::RemoveTag(tag) => format!("Remove +{} from {}?", tag, which),
+            Self::Project(Some(project)) => format!("Move {} to project {}?", which, project),
+            Self::Project(None) => format!("Remove {} from their project?", which),
+            Self::Wait(Some(date)) => format!("Hide {} until {}?", which, format_date(date)),
+            Self::Wait(None) => format!("Stop {} waiting?", which),
+        }
+    }
+
+    /// What happens to a task, when that differs between the tasks.
+    fn effect(&self, task: &Task) -> Option<String> {
+        match self {
+            Self::Done | Self::Delete => None,
+            Self::AddTag(tag) => task.has_tag(tag).then(|| "already tagged".to_string()),
+            Self::RemoveTag(tag) => (!task.has_tag(tag)).then(|| "not tagged".to_string()),
+            Self::Project(project) => Some(format!(
+                "{} → {}",
+                task.project.as_deref().unwrap_or("(none)"),
+                project.as_deref().unwrap_or("(none)")
+            )),
+            Self::Wait(date) => {
+                let show = |date: &Option<ParsableDateTime>| {
+                    date.as_ref().map_or("(none)".to_string(), format_date)
+                };
+                Some(format!("{} → {}", show(&task.wait), show(date)))
+            },
+        }
+    }
+
+    fn apply(&self, task: &mut Task) {
+        match self {
+            Self::Done => task.status = "completed".to_string(),
+            Self::Delete => task.status = "deleted".to_string(),
+            Self::AddTag(tag) =>
+                if !task.has_tag(tag) {
+                    task.tags.get_or_insert_with(Vec::new).push(tag.clone());
+                },
+            Self::RemoveTag(tag) => {
+                if let Some(tags) = &mut task.tags {
+                    tags.retain(|other| other != tag);
+                }
+                task.tags = task.tags.take().filter(|tags| !tags.is_empty());
+            },
+            Self::Project(project) => task.project.clone_from(project),
+            Self::Wait(date) => task.wait.clone_from(date),
+        }
+    }
+}
+
+fn format_date(date: &ParsableDateTime) -> String {
+    date.0.format("%Y-%m-%d").to_string()
+}
+
+/// The actions which need something typed before they can be confirmed
+#[derive(Clone, Copy)]
+pub(crate) enum Field {
+    AddTag,
+    RemoveTag,
+    Project,
+    Wait,
+}
+
+impl Field {
+    fn title(self) -> &'static str {
+        match self {
+            Self::AddTag => "Add tag",
+            Self::RemoveTag => "Remove tag",
+            Self::Project => "Set project (empty to remove)",
+            Self::Wait => "Wait until (empty to stop waiting)",
+        }
+    }
+}
+
+/// Reads the tag, project or date for a bulk action.
+pub(crate) struct BulkPrompt {
+    field: Field,
+    input: LineInput,
+    /// Set when the input is not valid, e.g. a date taskwarrior doesn't
+    /// understand
+    error: Option<String>,
+}
+
+impl BulkPrompt {
+    pub(crate) fn new(field: Field) -> Self {
+        Self {
+            field,
+            input: LineInput::default(),
+            error: None,
+        }
+    }
+
+    fn action(&self) -> Result<Action, String> {
+        let text = self.input.text().trim();
+        let tag = || {
+            let tag = text.trim_start_matches('+');
+            if tag.is_empty() || tag.contains(char::is_whitespace) {
+                Err("a single tag is required".to_string())
+            } else {
+                Ok(tag.to_string())
+            }
+        };
+        match self.field {
+            Field::AddTag => tag().map(Action::AddTag),
+            Field::RemoveTag => tag().map(Action::RemoveTag),
+            Field::Project => Ok(Action::Project(
+                Some(text.to_string()).filter(|project| !project.is_empty()),
+            )),
+            Field::Wait if text.is_empty() => Ok(Action::Wait(None)),
+            Field::Wait => taskwarrior::calc_date(text)
+                .map(|date| Action::Wait(Some(date)))
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl Mode for BulkPrompt {
+    fn name(&self) -> &'static str {
+        "BULK"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let (main, prompt) = prompt_layout(area);
+        common_render(frame, common_state, &[Modifier::DIM], main);
+        let title = match &self.error {
+            Some(error) => format!("{} ({})", self.field.title(), error),
+            None => self.field.title().to_string(),
+        };
+        render_prompt(frame, &common_state.theme, &title, &self.input, prompt);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Esc =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                }),
+            Key::Char('\n') => match self.action() {
+                Ok(action) =>
+                    return Ok(ActionResult {
+                        new_mode: Some(Box::new(Confirm::new(action, common_state))),
+                        ..ActionResult::default()
+                    }),
+                Err(error) => self.error = Some(error),
+            },
+            key =>
+                if self.input.handle(key) {
+                    self.error = None;
+                },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+/// Lists what an action will do to each of the marked tasks (or the selected
+/// one), and applies it once confirmed.
+pub(crate) struct Confirm {
+    action: Action,
+    /// The tasks the action applies to, by their index
+    tasks:  Vec<usize>,
+}
+
+impl Confirm {
+    pub(crate) fn new(action: Action, common_state: &CommonState) -> Self {
+        Self {
+            action,
+            tasks: common_state.targets(),
+        }
+    }
+}
+
+impl Mode for Confirm {
+    fn name(&self) -> &'static str {
+        "CONFIRM"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let layout = default_layout(area);
+        render_tasks(frame, common_state, &[Modifier::DIM], layout[0]);
+
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|index| &common_state.tasks[*index])
+            .collect::<Vec<_>>();
+        let mut lines = vec![
+            Spans::from("CONFIRM (ENTER) or CANCEL (ESC)"),
+            Spans::default(),
+        ];
+        lines.extend(tasks.iter().map(|task| {
+            let mut spans = vec![Span::raw(format!("{:>4} {}", task.id, task.description))];
+            if let Some(effect) = self.action.effect(task) {
+                spans.push(Span::styled(format!("  {}", effect), common_state.theme.hint));
+            }
+            Spans::from(spans)
+        }));
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(self.action.question(&tasks))
+                .style(common_state.theme.dialog)
+                .borders(Borders::ALL),
+        );
+
+        frame.render_widget(paragraph, layout[1]);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Esc | Key::Ctrl('f') =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                }),
+            Key::Char('\n') => {
+                for index in &self.tasks {
+                    self.action.apply(&mut common_state.tasks[*index]);
+                }
+                common_state.clear_marks();
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    should_flush: true,
+                    ..ActionResult::default()
+                });
+            },
+            _ => {},
+        }
+        Ok(ActionResult::default())
+    }
+
+    fn allows_reload(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "uuid": "1a2b3c4d-1111-2222-3333-444455556666",
+            "description": "Write the report",
+            "status": "pending",
+            "project": "work",
+            "tags": ["taskn"],
+        }))
+        .unwrap()
+    }
+
+    fn applied(action: &Action) -> Task {
+        let mut task = task();
+        action.apply(&mut task);
+        task
+    }
+
+    #[test]
+    fn test_apply_status() {
+        // taskwarrior only knows `completed`, and would read `done` as something else
+        assert_eq!(applied(&Action::Done).status, "completed");
+        assert_eq!(applied(&Action::Delete).status, "deleted");
+    }
+
+    #[test]
+    fn test_apply_tags() {
+        let tags = |action| applied(&action).tags;
+        assert_eq!(tags(Action::AddTag("bug".to_string())), Some(vec![
+            "taskn".to_string(),
+            "bug".to_string(),
+        ]));
+        assert_eq!(tags(Action::AddTag("taskn".to_string())), Some(vec!["taskn".to_string()]));
+        assert_eq!(tags(Action::RemoveTag("taskn".to_string())), None);
+    }
+
+    #[test]
+    fn test_apply_project() {
+        assert_eq!(applied(&Action::Project(None)).project, None);
+        assert_eq!(
+            applied(&Action::Project(Some("home".to_string()))).project,
+            Some("home".to_string())
+        );
+    }
+}
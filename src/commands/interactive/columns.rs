@@ -0,0 +1,329 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Local};
+
+use super::CommonState;
+use crate::{
+    config,
+    taskwarrior::{self, Task},
+};
+
+/// The attributes which can be shown as columns of the task list
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Column {
+    Id,
+    /// The position of the task in the manual order
+    Order,
+    Project,
+    Tags,
+    Due,
+    Priority,
+    Urgency,
+    /// The time since the task was created
+    Age,
+    /// Whether the task has a note
+    Note,
+    Description,
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    /// Parses the name of a column. Taskwarrior's names are understood too,
+    /// ignoring their format, so `entry.age` is [Column::Age].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase();
+        let base = name.split('.').next().unwrap_or_default();
+        match base {
+            "id" => Ok(Self::Id),
+            "order" | "estimate" => Ok(Self::Order),
+            "project" => Ok(Self::Project),
+            "tags" => Ok(Self::Tags),
+            "due" => Ok(Self::Due),
+            "priority" => Ok(Self::Priority),
+            "urgency" => Ok(Self::Urgency),
+            "age" | "entry" => Ok(Self::Age),
+            "note" => Ok(Self::Note),
+            "description" => Ok(Self::Description),
+            _ => Err(format!("unknown column '{}'", s)),
+        }
+    }
+}
+
+impl Column {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Id => "ID",
+            Self::Order => "Order",
+            Self::Project => "Project",
+            Self::Tags => "Tags",
+            Self::Due => "Due",
+            Self::Priority => "P",
+            Self::Urgency => "Urg",
+            Self::Age => "Age",
+            Self::Note => "N",
+            Self::Description => "Description",
+        }
+    }
+
+    /// The most characters the column takes up. The description is given
+    /// whatever is left over.
+    pub(crate) fn max_width(self) -> usize {
+        match self {
+            Self::Project | Self::Tags => 16,
+            Self::Description => usize::MAX,
+            _ => 10,
+        }
+    }
+
+    /// The text of the column for a task, before it is truncated.
+    pub(crate) fn text(self, task: &Task, common_state: &CommonState) -> String {
+        match self {
+            Self::Id => task.id.to_string(),
+            Self::Order => task
+                .manual_order()
+                .map(|order| order.to_string())
+                .unwrap_or_default(),
+            Self::Project => task.project.clone().unwrap_or_default(),
+            Self::Tags => task.tags.as_deref().unwrap_or_default().join(" "),
+            Self::Due => task
+                .due
+                .as_ref()
+                .map(|due| due.0.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            Self::Priority => task.priority.clone().unwrap_or_default(),
+            Self::Urgency => task
+                .urgency
+                .map(|urgency| format!("{:.1}", urgency))
+                .unwrap_or_default(),
+            Self::Age => task
+                .entry
+                .as_ref()
+                .map(|entry| age(Local::now() - entry.0))
+                .unwrap_or_default(),
+            Self::Note =>
+                if common_state.contents_of(&task.uuid).is_empty() {
+                    String::new()
+                } else {
+                    "*".to_string()
+                },
+            Self::Description => task.description.clone(),
+        }
+    }
+}
+
+/// The columns of the task list, along with their headers
+pub(crate) type Columns = Vec<(Column, String)>;
+
+/// Reads the columns from the config, or from the taskwarrior report it names.
+pub(crate) fn from_config(config: &config::Interactive) -> Result<Columns> {
+    if let Some(report) = &config.report {
+        return report_columns(report);
+    }
+    let columns = config
+        .columns
+        .iter()
+        .map(|name| name.parse::<Column>().map_err(|e| anyhow!("{} in config", e)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(with_labels(columns, &config.labels))
+}
+
+/// Reads the `columns` and `labels` of a taskwarrior report. Columns which
+/// taskn doesn't know, like `depends`, are left out.
+fn report_columns(report: &str) -> Result<Columns> {
+    let setting = |name: &str| {
+        taskwarrior::config_value(&format!("report.{}.{}", report, name))
+            .with_context(|| format!("error reading report '{}'", report))
+    };
+    let names = setting("columns")?;
+    let labels = setting("labels")?;
+    let labels = labels.split(',').collect::<Vec<_>>();
+
+    let columns = names
+        .split(',')
+        .enumerate()
+        .filter_map(|(i, name)| {
+            let column = name.parse::<Column>().ok()?;
+            let label = labels.get(i).filter(|label| !label.is_empty());
+            Some((column, label.map_or(column.label(), |label| *label).to_string()))
+        })
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return Err(anyhow!("report '{}' has no columns taskn can show", report));
+    }
+    Ok(columns)
+}
+
+fn with_labels(columns: Vec<Column>, labels: &[String]) -> Columns {
+    columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let label = labels.get(i).map_or(column.label(), String::as_str);
+            (column, label.to_string())
+        })
+        .collect()
+}
+
+/// The number of characters the description keeps when the other columns
+/// don't fit
+const MIN_DESCRIPTION: usize = 16;
+
+/// Decides how wide each column is drawn, given the text of every row. Columns
+/// are as wide as their contents and the description gets the rest. When that
+/// leaves the description too little room, the widest columns are narrowed.
+pub(crate) fn widths(columns: &[(Column, String)], rows: &[&[String]], total: usize) -> Vec<usize> {
+    let mut widths = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (column, label))| {
+            rows.iter()
+                .map(|cells| cells[i].chars().count())
+                .chain(Some(label.chars().count()))
+                .max()
+                .unwrap_or_default()
+                .min(column.max_width())
+        })
+        .collect::<Vec<_>>();
+
+    let is_description = |i: usize| columns[i].0 == Column::Description;
+    let has_description = (0..columns.len()).any(is_description);
+    // every column but the first is spaced from the one before it
+    let fixed = |widths: &[usize]| {
+        (0..widths.len())
+            .filter(|i| !is_description(*i))
+            .map(|i| widths[i] + 1)
+            .sum::<usize>()
+    };
+    if has_description {
+        while fixed(&widths) + MIN_DESCRIPTION > total {
+            let widest = (0..widths.len())
+                .filter(|i| !is_description(*i))
+                .max_by_key(|i| widths[*i]);
+            match widest {
+                Some(widest) if widths[widest] > 3 => widths[widest] -= 1,
+                _ => break,
+            }
+        }
+    }
+
+    let rest = total.saturating_sub(fixed(&widths));
+    for (i, width) in widths.iter_mut().enumerate() {
+        if is_description(i) {
+            *width = rest;
+        }
+    }
+    widths
+}
+
+/// Formats a duration the way taskwarrior shows ages, e.g. `3d` or `5w`.
+pub(crate) fn age(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let (amount, unit) = match seconds {
+        s if s < 60 => (s, "s"),
+        s if s < 60 * 60 => (s / 60, "min"),
+        s if s < 60 * 60 * 24 => (s / (60 * 60), "h"),
+        s if s < 60 * 60 * 24 * 14 => (s / (60 * 60 * 24), "d"),
+        s if s < 60 * 60 * 24 * 90 => (s / (60 * 60 * 24 * 7), "w"),
+        s if s < 60 * 60 * 24 * 365 => (s / (60 * 60 * 24 * 30), "mo"),
+        s => (s / (60 * 60 * 24 * 365), "y"),
+    };
+    format!("{}{}", amount, unit)
+}
+
+/// Shortens text to a number of characters, marking where it was cut with an
+/// ellipsis.
+pub(crate) fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut truncated = text.chars().take(width - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(columns: &[Column]) -> Columns {
+        with_labels(columns.to_vec(), &[])
+    }
+
+    fn row(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("id".parse(), Ok(Column::Id));
+        assert_eq!(" Estimate ".parse(), Ok(Column::Order));
+        assert_eq!("entry.age".parse(), Ok(Column::Age));
+        assert_eq!("due.relative".parse(), Ok(Column::Due));
+        assert!("bogus".parse::<Column>().is_err());
+    }
+
+    #[test]
+    fn test_with_labels() {
+        let labeled = with_labels(vec![Column::Id, Column::Due], &["#".to_string()]);
+        assert_eq!(labeled, vec![
+            (Column::Id, "#".to_string()),
+            (Column::Due, "Due".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_widths() {
+        let columns = columns(&[Column::Id, Column::Project, Column::Description]);
+        let first = row(&["1", "work.api", "First"]);
+        let second = row(&["12", "x", "Second"]);
+        let rows = [first.as_slice(), second.as_slice()];
+        // the description takes whatever the other columns leave
+        assert_eq!(widths(&columns, &rows, 40), vec![2, 8, 28]);
+        assert_eq!(widths(&columns, &[], 40), vec![2, 7, 29]);
+    }
+
+    #[test]
+    fn test_widths_narrow_columns_for_the_description() {
+        let columns = columns(&[Column::Id, Column::Project, Column::Description]);
+        let cells = row(&["1", "a.very.long.project.name", "First"]);
+        let rows = [cells.as_slice()];
+        assert_eq!(widths(&columns, &rows, 80), vec![2, 16, 60]);
+        assert_eq!(widths(&columns, &rows, 30), vec![2, 10, 16]);
+        // columns are never narrowed past three characters
+        assert_eq!(widths(&columns, &rows, 10), vec![2, 3, 3]);
+    }
+
+    #[test]
+    fn test_widths_without_description() {
+        let columns = columns(&[Column::Id, Column::Project]);
+        let cells = row(&["123", "home"]);
+        assert_eq!(widths(&columns, &[cells.as_slice()], 4), vec![3, 7]);
+    }
+
+    #[test]
+    fn test_age() {
+        assert_eq!(age(Duration::seconds(59)), "59s");
+        assert_eq!(age(Duration::minutes(5)), "5min");
+        assert_eq!(age(Duration::hours(3)), "3h");
+        assert_eq!(age(Duration::days(13)), "13d");
+        assert_eq!(age(Duration::days(20)), "2w");
+        assert_eq!(age(Duration::days(100)), "3mo");
+        assert_eq!(age(Duration::days(800)), "2y");
+        // clocks can disagree slightly about the future
+        assert_eq!(age(Duration::seconds(-5)), "0s");
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello", 4), "hel…");
+        assert_eq!(truncate("héllo wörld", 6), "héllo…");
+        assert_eq!(truncate("hello", 1), "…");
+        assert_eq!(truncate("hello", 0), "");
+    }
+}
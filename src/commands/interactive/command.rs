@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use termion::event::Key;
+use tui::{
+    layout::Rect,
+    style::Modifier,
+    widgets::{Clear, Paragraph, Wrap},
+};
+
+use super::{
+    common_render,
+    input::{Completion, LineInput},
+    preview::popup,
+    prompt_layout, render_prompt, ActionResult, CommonState, Frame, Mode, Normal,
+};
+use crate::{
+    journal::Change,
+    opt::Opt,
+    taskwarrior::{self, Task},
+};
+
+/// The words completed in [CommandPrompt], read from taskwarrior the first
+/// time `Tab` is pressed
+struct Words {
+    commands:   Vec<String>,
+    /// The names of attributes, followed by a `:`
+    attributes: Vec<String>,
+    projects:   Vec<String>,
+    tags:       Vec<String>,
+}
+
+impl Words {
+    fn load() -> Result<Self> {
+        Ok(Self {
+            commands:   taskwarrior::commands().context("error listing commands")?,
+            attributes: taskwarrior::attributes()
+                .context("error listing attributes")?
+                .into_iter()
+                .map(|attribute| format!("{}:", attribute))
+                .collect(),
+            projects:   taskwarrior::projects().context("error listing projects")?,
+            tags:       taskwarrior::tags().context("error listing tags")?,
+        })
+    }
+}
+
+/// Runs a taskwarrior command, e.g. `modify +urgent` or `annotate call back`,
+/// on the marked tasks or the selected one.
+pub(crate) struct CommandPrompt {
+    input:      LineInput,
+    words:      Option<Words>,
+    completion: Option<Completion>,
+    /// Set when the command could not be parsed
+    error:      Option<String>,
+}
+
+impl CommandPrompt {
+    pub(crate) fn new() -> Self {
+        Self {
+            input:      LineInput::default(),
+            words:      None,
+            completion: None,
+            error:      None,
+        }
+    }
+
+    /// Completes the word at the end of the line: a command when it's the
+    /// first word, a tag after `+` or `-`, a project after `project:`, and
+    /// otherwise the name of an attribute. Repeated presses cycle through the
+    /// candidates.
+    fn complete(&mut self) -> Result<()> {
+        if let Some(completion) = &mut self.completion {
+            completion.next();
+        } else {
+            if self.words.is_none() {
+                self.words = Some(Words::load()?);
+            }
+            let words = match &self.words {
+                Some(words) => words,
+                None => return Ok(()),
+            };
+            let text = self.input.text();
+            let start = text.rfind(' ').map_or(0, |space| space + 1);
+            let (before, word) = text.split_at(start);
+            self.completion = if before.trim().is_empty() {
+                Completion::new(before, word, &words.commands)
+            } else if word.starts_with(['+', '-']) {
+                Completion::new(&text[..=start], &word[1..], &words.tags)
+            } else if let Some((name, project)) = word.split_once(':') {
+                // taskwarrior takes abbreviated attribute names, like `pro:`
+                if name.len() >= 3 && "project".starts_with(name) {
+                    Completion::new(&text[..=start + name.len()], project, &words.projects)
+                } else {
+                    None
+                }
+            } else {
+                Completion::new(before, word, &words.attributes)
+            };
+        }
+
+        if let Some(completion) = &self.completion {
+            self.input.set(&completion.line());
+        }
+        Ok(())
+    }
+}
+
+impl Mode for CommandPrompt {
+    fn name(&self) -> &'static str {
+        "COMMAND"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let (main, prompt) = prompt_layout(area);
+        common_render(frame, common_state, &[Modifier::DIM], main);
+        let which = match common_state.targets().as_slice() {
+            [index] => format!("task {}", common_state.tasks[*index].id),
+            targets => format!("{} tasks", targets.len()),
+        };
+        let title = match (&self.error, &self.completion) {
+            (Some(error), _) => format!("Command for {} ({})", which, error),
+            (None, Some(completion)) => completion.candidates.join(" "),
+            (None, None) => format!("Command for {} (TAB completes)", which),
+        };
+        render_prompt(frame, &common_state.theme, &title, &self.input, prompt);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        if key != Key::Char('\t') {
+            self.completion = None;
+        }
+        match key {
+            Key::Esc => {
+                common_state.command_history.reset();
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                });
+            },
+            Key::Char('\t') =>
+                if let Err(e) = self.complete() {
+                    self.error = Some(format!("{:#}", e));
+                },
+            Key::Char('\n') => match shell_words::split(self.input.text()) {
+                Ok(args) if args.is_empty() => {},
+                Ok(args) => {
+                    common_state.command_history.push(self.input.text());
+                    return run(common_state, &args);
+                },
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            key =>
+                if common_state.command_history.handle(key, &mut self.input)
+                    || self.input.handle(key)
+                {
+                    self.error = None;
+                },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+/// Runs a command on the marked tasks, or the selected one. The command goes
+/// around taskn, so what it changed is found by loading the tasks again, to
+/// be kept in the journal. Anything it printed is shown in [Output].
+fn run(common_state: &mut CommonState, args: &[String]) -> Result<ActionResult> {
+    let tasks = common_state
+        .targets()
+        .into_iter()
+        .map(|index| common_state.tasks[index].clone())
+        .collect::<Vec<_>>();
+    let uuids = tasks.iter().map(|task| task.uuid.clone()).collect::<Vec<_>>();
+    let command = format!("task {}", shell_words::join(args));
+    let output =
+        taskwarrior::run(&uuids, args).with_context(|| format!("error running `{}`", command))?;
+
+    let after = Task::get(uuids.iter()).context("error loading the tasks again")?;
+    let changes = tasks
+        .iter()
+        .filter_map(|before| {
+            let after = after.iter().find(|after| after.uuid == before.uuid)?;
+            Change::new(before, after)
+        })
+        .collect();
+    common_state.record(changes)?;
+    common_state.clear_marks();
+
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let new_mode: Box<dyn Mode> = if !output.status.success() {
+        Box::new(Output::new(format!("{} failed", command), &text))
+    } else if text.trim().is_empty() {
+        Box::new(Normal)
+    } else {
+        Box::new(Output::new(command, &text))
+    };
+    Ok(ActionResult {
+        new_mode: Some(new_mode),
+        should_load: true,
+        ..ActionResult::default()
+    })
+}
+
+/// Shows what a command printed, in a popup over the task list.
+pub(crate) struct Output {
+    title:  String,
+    lines:  Vec<String>,
+    scroll: u16,
+}
+
+impl Output {
+    fn new(title: String, text: &str) -> Self {
+        Self {
+            title,
+            lines: text.trim_end().lines().map(String::from).collect(),
+            scroll: 0,
+        }
+    }
+}
+
+impl Mode for Output {
+    fn name(&self) -> &'static str {
+        "OUTPUT"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        common_render(frame, common_state, &[Modifier::DIM], area);
+
+        #[allow(clippy::cast_possible_truncation)]
+        let height = (self.lines.len() + 2).min(usize::from(area.height.saturating_sub(4))) as u16;
+        let area = popup(area, height);
+        let paragraph = Paragraph::new(self.lines.join("\n"))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(
+                common_state
+                    .theme
+                    .block()
+                    .title(format!("{} (j/k to scroll, any other key to close)", self.title)),
+            );
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        _common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Char('j') | Key::Down => self.scroll = self.scroll.saturating_add(1),
+            Key::Char('k') | Key::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                }),
+        }
+        Ok(ActionResult::default())
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use termion::event::Key;
+use tui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+use super::{
+    default_layout, render_tasks, save_task, ActionResult, CommonState, Frame, Mode, Normal,
+};
+use crate::{
+    journal::{Change, Snapshot},
+    opt::Opt,
+    taskwarrior::Task,
+};
+
+/// A task which was changed in taskn and, since it was loaded, by something
+/// else too
+pub(crate) struct Conflict {
+    /// The task as it was loaded
+    pub(crate) base:   Task,
+    pub(crate) mine:   Task,
+    /// The task as taskwarrior has it now
+    pub(crate) theirs: Task,
+}
+
+impl Conflict {
+    /// Takes the attributes changed in taskn from [Conflict::mine], and the
+    /// rest from [Conflict::theirs].
+    fn merge(&self) -> Task {
+        let (base, mine) = (&self.base, &self.mine);
+        let mut merged = self.theirs.clone();
+        if mine.description != base.description {
+            merged.description.clone_from(&mine.description);
+        }
+        if mine.status != base.status {
+            merged.status.clone_from(&mine.status);
+        }
+        if mine.project != base.project {
+            merged.project.clone_from(&mine.project);
+        }
+        if mine.tags != base.tags {
+            merged.tags.clone_from(&mine.tags);
+        }
+        if mine.priority != base.priority {
+            merged.priority.clone_from(&mine.priority);
+        }
+        if mine.due != base.due {
+            merged.due.clone_from(&mine.due);
+        }
+        if mine.wait != base.wait {
+            merged.wait.clone_from(&mine.wait);
+        }
+        if mine.scheduled != base.scheduled {
+            merged.scheduled.clone_from(&mine.scheduled);
+        }
+        if mine.estimate != base.estimate {
+            merged.estimate.clone_from(&mine.estimate);
+        }
+        merged
+    }
+
+    /// The attributes which differ between the two changes, along with their
+    /// value as loaded, in taskn and in taskwarrior.
+    fn differences(&self) -> Vec<(&'static str, String, String, String)> {
+        let base = Snapshot::of(&self.base).fields();
+        let mine = Snapshot::of(&self.mine).fields();
+        let theirs = Snapshot::of(&self.theirs).fields();
+        base.into_iter()
+            .zip(mine)
+            .zip(theirs)
+            .filter(|((_, (_, mine)), (_, theirs))| mine != theirs)
+            .map(|(((name, base), (_, mine)), (_, theirs))| (name, base, mine, theirs))
+            .collect()
+    }
+}
+
+/// Asks how to settle each conflict left over from flushing: by keeping the
+/// changes made in taskn, keeping the ones made elsewhere, or merging them.
+pub(crate) struct Resolve {
+    conflicts: Vec<Conflict>,
+    /// The conflict being settled
+    current:   usize,
+}
+
+impl Resolve {
+    pub(crate) fn new(conflicts: Vec<Conflict>) -> Self {
+        Self {
+            conflicts,
+            current: 0,
+        }
+    }
+}
+
+impl Mode for Resolve {
+    fn name(&self) -> &'static str {
+        "CONFLICT"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let layout = default_layout(area);
+        render_tasks(frame, common_state, &[Modifier::DIM], layout[0]);
+
+        let conflict = &self.conflicts[self.current];
+        let mut lines = vec![
+            Spans::from("KEEP MINE (m), KEEP THEIRS (t) or MERGE (b)"),
+            Spans::from(Span::styled(
+                "merging keeps the attributes changed here, and takes the rest from \
+                 taskwarrior",
+                common_state.theme.hint,
+            )),
+        ];
+        for (name, base, mine, theirs) in conflict.differences() {
+            lines.push(Spans::default());
+            lines.push(Spans::from(Span::styled(name, common_state.theme.label)));
+            lines.push(Spans::from(format!("  loaded: {}", base)));
+            lines.push(Spans::from(format!("  mine:   {}", mine)));
+            lines.push(Spans::from(format!("  theirs: {}", theirs)));
+        }
+        let title = format!(
+            "Task {} was changed elsewhere ({}/{})",
+            conflict.theirs.id,
+            self.current + 1,
+            self.conflicts.len()
+        );
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title(title)
+                .style(common_state.theme.dialog)
+                .borders(Borders::ALL),
+        );
+        frame.render_widget(paragraph, layout[1]);
+    }
+
+    fn update(
+        &mut self,
+        opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        let conflict = &self.conflicts[self.current];
+        let resolved = match key {
+            Key::Char('m') => Some(conflict.mine.clone()),
+            Key::Char('t') => None,
+            Key::Char('b') => Some(conflict.merge()),
+            _ => return Ok(ActionResult::default()),
+        };
+        if let Some(task) = resolved {
+            save_task(opt, &task, &conflict.theirs)?;
+            common_state.record(Change::new(&conflict.theirs, &task).into_iter().collect())?;
+        }
+
+        self.current += 1;
+        if self.current < self.conflicts.len() {
+            return Ok(ActionResult::default());
+        }
+        Ok(ActionResult {
+            new_mode: Some(Box::new(Normal)),
+            should_load: true,
+            ..ActionResult::default()
+        })
+    }
+
+    fn allows_reload(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,463 @@
+use std::{cell::Cell, slice};
+
+use anyhow::Result;
+use termion::event::Key;
+use tui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Paragraph},
+};
+
+use super::{default_layout, render_tasks, ActionResult, CommonState, Frame, Mode, Normal};
+use crate::{
+    commands::edit,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskwarrior::Task,
+};
+
+/// The number of lines moved by `PageUp` and `PageDown`
+const PAGE: isize = 10;
+
+/// The kinds of edits which are undone together when they follow each other
+#[derive(Clone, Copy, PartialEq)]
+enum Edit {
+    Insert,
+    Delete,
+    Newline,
+}
+
+/// A multi-line text area with a cursor, which soft wraps its lines to the
+/// width it is drawn at.
+pub(crate) struct TextArea {
+    lines:     Vec<String>,
+    /// The line the cursor is on
+    row:       usize,
+    /// The position of the cursor in its line, counted in characters
+    col:       usize,
+    /// Snapshots of the lines and cursor from before each group of edits
+    undo:      Vec<(Vec<String>, usize, usize)>,
+    last_edit: Option<Edit>,
+    /// The first wrapped line which is shown, updated while drawing to keep
+    /// the cursor visible
+    scroll:    Cell<usize>,
+}
+
+impl TextArea {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut lines = text.lines().map(String::from).collect::<Vec<_>>();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        Self {
+            lines,
+            row: 0,
+            col: 0,
+            undo: vec![],
+            last_edit: None,
+            scroll: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].chars().count()
+    }
+
+    fn byte_offset(&self, row: usize, col: usize) -> usize {
+        self.lines[row]
+            .char_indices()
+            .nth(col)
+            .map_or(self.lines[row].len(), |(offset, _)| offset)
+    }
+
+    /// Records the text before an edit, unless it continues the previous edit.
+    fn begin(&mut self, edit: Edit) {
+        if self.last_edit != Some(edit) {
+            self.undo.push((self.lines.clone(), self.row, self.col));
+        }
+        self.last_edit = Some(edit);
+    }
+
+    fn undo(&mut self) {
+        if let Some((lines, row, col)) = self.undo.pop() {
+            self.lines = lines;
+            self.row = row;
+            self.col = col;
+        }
+        self.last_edit = None;
+    }
+
+    /// Applies a key which moves the cursor or edits the text. Returns whether
+    /// the key was used.
+    pub(crate) fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char('\n') => {
+                self.begin(Edit::Newline);
+                let offset = self.byte_offset(self.row, self.col);
+                let rest = self.lines[self.row].split_off(offset);
+                self.lines.insert(self.row + 1, rest);
+                self.row += 1;
+                self.col = 0;
+            },
+            Key::Char('\t') => {
+                self.begin(Edit::Insert);
+                let offset = self.byte_offset(self.row, self.col);
+                self.lines[self.row].insert_str(offset, "    ");
+                self.col += 4;
+            },
+            Key::Char(c) => {
+                self.begin(Edit::Insert);
+                let offset = self.byte_offset(self.row, self.col);
+                self.lines[self.row].insert(offset, c);
+                self.col += 1;
+            },
+            Key::Backspace =>
+                if self.col > 0 {
+                    self.begin(Edit::Delete);
+                    self.col -= 1;
+                    let offset = self.byte_offset(self.row, self.col);
+                    self.lines[self.row].remove(offset);
+                } else if self.row > 0 {
+                    self.begin(Edit::Delete);
+                    let line = self.lines.remove(self.row);
+                    self.row -= 1;
+                    self.col = self.line_len(self.row);
+                    self.lines[self.row].push_str(&line);
+                },
+            Key::Delete =>
+                if self.col < self.line_len(self.row) {
+                    self.begin(Edit::Delete);
+                    let offset = self.byte_offset(self.row, self.col);
+                    self.lines[self.row].remove(offset);
+                } else if self.row + 1 < self.lines.len() {
+                    self.begin(Edit::Delete);
+                    let line = self.lines.remove(self.row + 1);
+                    self.lines[self.row].push_str(&line);
+                },
+            Key::Ctrl('z') => {
+                self.undo();
+                return true;
+            },
+            key => {
+                if !self.move_cursor(key) {
+                    return false;
+                }
+                self.last_edit = None;
+            },
+        }
+        true
+    }
+
+    fn move_cursor(&mut self, key: Key) -> bool {
+        match key {
+            Key::Left =>
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_len(self.row);
+                },
+            Key::Right =>
+                if self.col < self.line_len(self.row) {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                },
+            Key::Up => self.move_rows(-1),
+            Key::Down => self.move_rows(1),
+            Key::PageUp => self.move_rows(-PAGE),
+            Key::PageDown => self.move_rows(PAGE),
+            Key::Home | Key::Ctrl('a') => self.col = 0,
+            Key::End | Key::Ctrl('e') => self.col = self.line_len(self.row),
+            _ => return false,
+        }
+        true
+    }
+
+    fn move_rows(&mut self, rows: isize) {
+        let last = self.lines.len() - 1;
+        self.row = if rows < 0 {
+            self.row.saturating_sub(rows.unsigned_abs())
+        } else {
+            (self.row + rows.unsigned_abs()).min(last)
+        };
+        self.col = self.col.min(self.line_len(self.row));
+    }
+
+    /// Draws the text wrapped to the width of the area, scrolled so that the
+    /// cursor is visible.
+    #[allow(single_use_lifetimes)]
+    pub(crate) fn render<'a>(&self, frame: &mut Frame<'a>, block: Block<'_>, area: Rect) {
+        let inner = block.inner(area);
+        let width = usize::from(inner.width).max(1);
+        let height = usize::from(inner.height).max(1);
+
+        let mut lines = vec![];
+        let mut cursor_line = 0;
+        for (row, line) in self.lines.iter().enumerate() {
+            let chars = line.chars().collect::<Vec<_>>();
+            let segments = wrap(&chars, width);
+            for (i, &(start, end)) in segments.iter().enumerate() {
+                let last = i + 1 == segments.len();
+                let has_cursor =
+                    row == self.row && start <= self.col && (self.col < end || last);
+                if has_cursor {
+                    cursor_line = lines.len();
+                    let at = self.col - start;
+                    let under_cursor = chars.get(self.col).map_or(' ', |c| *c);
+                    lines.push(Spans::from(vec![
+                        Span::raw(chars[start..start + at].iter().collect::<String>()),
+                        Span::styled(
+                            under_cursor.to_string(),
+                            Style::default().add_modifier(Modifier::REVERSED),
+                        ),
+                        Span::raw(
+                            chars[(start + at + 1).min(end)..end]
+                                .iter()
+                                .collect::<String>(),
+                        ),
+                    ]));
+                } else {
+                    lines.push(Spans::from(chars[start..end].iter().collect::<String>()));
+                }
+            }
+        }
+
+        let mut scroll = self.scroll.get();
+        if cursor_line < scroll {
+            scroll = cursor_line;
+        } else if cursor_line >= scroll + height {
+            scroll = cursor_line + 1 - height;
+        }
+        self.scroll.set(scroll);
+
+        let visible = lines.into_iter().skip(scroll).take(height).collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(visible).block(block), area);
+    }
+}
+
+/// Splits a line into the ranges of characters shown on each wrapped line,
+/// breaking after spaces where possible.
+pub(super) fn wrap(chars: &[char], width: usize) -> Vec<(usize, usize)> {
+    let mut segments = vec![];
+    let mut start = 0;
+    while chars.len() - start > width {
+        let end = chars[start..start + width]
+            .iter()
+            .rposition(|c| *c == ' ')
+            .map_or(start + width, |space| start + space + 1);
+        segments.push((start, end));
+        start = end;
+    }
+    segments.push((start, chars.len()));
+    segments
+}
+
+/// Edits the selected task's note inside of the preview pane, for when an
+/// external editor is too heavy or is not available.
+pub(crate) struct NoteEditor {
+    task:            Task,
+    text:            TextArea,
+    original:        String,
+    /// Set after `Esc` is pressed with unsaved changes
+    confirm_discard: bool,
+}
+
+impl NoteEditor {
+    pub(crate) fn new(task: &Task, contents: &str) -> Self {
+        let text = TextArea::new(contents);
+        Self {
+            task: task.clone(),
+            original: text.text(),
+            text,
+            confirm_discard: false,
+        }
+    }
+
+    fn save(&self, opt: &Opt) -> Result<()> {
+        save_note(opt, &self.task, &self.text.text())
+    }
+}
+
+/// Replaces the contents of a task's note, as if it was edited in the editor.
+pub(crate) fn save_note(opt: &Opt, task: &Task, text: &str) -> Result<()> {
+    let mut resolver = Resolver::new(opt)?;
+    resolver.rename(task)?;
+    resolver.prepare(task)?;
+    let path = resolver.note_path(task);
+    // like the editor, an empty note isn't created
+    if path.exists() || text.chars().any(|c| !c.is_whitespace()) {
+        notes::write(opt, &path, text)?;
+    }
+    edit::finish(&resolver, slice::from_ref(task))
+}
+
+impl Mode for NoteEditor {
+    fn name(&self) -> &'static str {
+        "EDIT"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let layout = default_layout(area);
+        render_tasks(frame, common_state, &[Modifier::DIM], layout[0]);
+
+        let title = if self.confirm_discard {
+            "Discard changes? (ESC discard, CTRL-S save)".to_string()
+        } else {
+            format!("Editing {} (CTRL-S save, ESC cancel)", self.task.description)
+        };
+        let block = common_state.theme.block().title(title);
+        self.text.render(frame, block, layout[1]);
+    }
+
+    fn update(
+        &mut self,
+        opt: &Opt,
+        _common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Ctrl('s') => {
+                if self.text.text() != self.original {
+                    self.save(opt)?;
+                }
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    should_load: true,
+                    ..ActionResult::default()
+                });
+            },
+            Key::Esc => {
+                if self.confirm_discard || self.text.text() == self.original {
+                    return Ok(ActionResult {
+                        new_mode: Some(Box::new(Normal)),
+                        ..ActionResult::default()
+                    });
+                }
+                self.confirm_discard = true;
+            },
+            key =>
+                if self.text.handle(key) {
+                    self.confirm_discard = false;
+                },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(text_area: &mut TextArea, text: &str) {
+        for c in text.chars() {
+            text_area.handle(Key::Char(c));
+        }
+    }
+
+    fn press(text_area: &mut TextArea, keys: &[Key]) {
+        for key in keys {
+            assert!(text_area.handle(*key));
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(TextArea::new("").text(), "\n");
+        assert_eq!(TextArea::new("a\nb\n").text(), "a\nb\n");
+    }
+
+    #[test]
+    fn test_typing() {
+        let mut text_area = TextArea::new("hello");
+        press(&mut text_area, &[Key::End]);
+        type_text(&mut text_area, " world\n\tx");
+        assert_eq!(text_area.text(), "hello world\n    x\n");
+    }
+
+    #[test]
+    fn test_edit_multi_byte_characters() {
+        let mut text_area = TextArea::new("héllo");
+        press(&mut text_area, &[Key::Right, Key::Right, Key::Backspace]);
+        type_text(&mut text_area, "e");
+        press(&mut text_area, &[Key::Delete]);
+        assert_eq!(text_area.text(), "helo\n");
+    }
+
+    #[test]
+    fn test_join_lines() {
+        let mut text_area = TextArea::new("ab\ncd\nef");
+        press(&mut text_area, &[Key::Down, Key::Backspace]);
+        type_text(&mut text_area, "-");
+        assert_eq!(text_area.text(), "ab-cd\nef\n");
+        press(&mut text_area, &[Key::End, Key::Delete]);
+        assert_eq!(text_area.text(), "ab-cdef\n");
+        // there's nothing to join at the very start and end
+        press(&mut text_area, &[Key::End, Key::Delete, Key::Home, Key::Up, Key::Backspace]);
+        assert_eq!(text_area.text(), "ab-cdef\n");
+    }
+
+    #[test]
+    fn test_moving_keeps_the_cursor_in_the_line() {
+        let mut text_area = TextArea::new("a long line\nab");
+        press(&mut text_area, &[Key::End, Key::Down]);
+        type_text(&mut text_area, "!");
+        press(&mut text_area, &[Key::PageUp, Key::Right, Key::Left, Key::Left]);
+        type_text(&mut text_area, "?");
+        assert_eq!(text_area.text(), "a ?long line\nab!\n");
+        assert!(!text_area.handle(Key::Esc));
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut text_area = TextArea::new("a");
+        press(&mut text_area, &[Key::End]);
+        type_text(&mut text_area, "bc");
+        press(&mut text_area, &[Key::Backspace, Key::Backspace]);
+        type_text(&mut text_area, "\nd");
+        // each run of the same kind of edit is undone at once
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "a\n\n");
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "a\n");
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "abc\n");
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "a\n");
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "a\n");
+        // the cursor goes back to where the edit started
+        type_text(&mut text_area, "x");
+        assert_eq!(text_area.text(), "ax\n");
+    }
+
+    #[test]
+    fn test_moving_ends_an_edit() {
+        let mut text_area = TextArea::new("");
+        type_text(&mut text_area, "ab");
+        press(&mut text_area, &[Key::Left]);
+        type_text(&mut text_area, "c");
+        assert_eq!(text_area.text(), "acb\n");
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "ab\n");
+        press(&mut text_area, &[Key::Ctrl('z')]);
+        assert_eq!(text_area.text(), "\n");
+    }
+
+    #[test]
+    fn test_wrap() {
+        let chars = |text: &str| text.chars().collect::<Vec<_>>();
+        assert_eq!(wrap(&chars("one two three"), 8), vec![(0, 8), (8, 13)]);
+        assert_eq!(wrap(&chars("abcdefghij"), 4), vec![(0, 4), (4, 8), (8, 10)]);
+        assert_eq!(wrap(&chars("fits"), 4), vec![(0, 4)]);
+        assert_eq!(wrap(&[], 4), vec![(0, 0)]);
+    }
+}
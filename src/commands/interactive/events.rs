@@ -1,49 +1,174 @@
 use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use signal_hook::{consts::signal::SIGWINCH, iterator::Signals};
-use std::{io, sync::mpsc, thread};
-use termion::{event::Key, input::TermRead};
+use std::{
+    cell::Cell,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use termion::{
+    event::{self, Key, MouseEvent},
+    input::TermRead,
+};
 
 pub(crate) enum Event {
     Key(Key),
+    Mouse(MouseEvent),
     Resize,
+    /// Tasks or notes were changed outside of taskn
+    Changed(Changed),
 }
 
+/// What was changed outside of taskn
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Changed {
+    Notes,
+    /// Taskwarrior's data, which may also have changed which notes are shown
+    Tasks,
+}
+
+/// The directories watched for changes made outside of taskn
+pub(crate) struct Watch {
+    /// Taskwarrior's data directory, if it could be found
+    pub(crate) tasks: Option<PathBuf>,
+    /// The root directory of the notes
+    pub(crate) notes: PathBuf,
+}
+
+/// How long to wait for more changes before reporting them, since a single
+/// `task` command writes several files
+const SETTLE: Duration = Duration::from_millis(150);
+
 pub(crate) struct Events {
-    rx: mpsc::Receiver<Event>,
+    rx:      mpsc::Receiver<Event>,
+    /// Lets the input thread read the next key
+    resume:  mpsc::Sender<()>,
+    /// Whether the input thread is waiting to be resumed
+    waiting: Cell<bool>,
 
     _input_thread:  thread::JoinHandle<()>,
     _signal_thread: thread::JoinHandle<()>,
+    /// Stops watching for changes once dropped
+    _watcher:       RecommendedWatcher,
 }
 
 impl Events {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(watch: &Watch) -> Result<Self> {
         let (tx, rx) = mpsc::channel();
-        Self {
+        let (resume, resume_rx) = mpsc::channel();
+        Ok(Self {
             rx,
-            _input_thread: make_input_thread(tx.clone()),
-            _signal_thread: make_signal_thread(tx),
-        }
+            resume,
+            waiting: Cell::new(false),
+            _input_thread: make_input_thread(tx.clone(), resume_rx),
+            _signal_thread: make_signal_thread(tx.clone()),
+            _watcher: make_watcher(tx, watch)?,
+        })
     }
 
+    /// Waits for the next event. Input is only read from stdin while waiting,
+    /// so that a program run in between (e.g. an editor) has stdin to itself.
     pub(crate) fn next(&self) -> Result<Event> {
-        self.rx
+        if self.waiting.replace(false) {
+            self.resume
+                .send(())
+                .context("error resuming the input thread")?;
+        }
+        let event = self
+            .rx
             .recv()
-            .context("error receiving next item in iterator")
+            .context("error receiving next item in iterator")?;
+        if let Event::Key(_) | Event::Mouse(_) = event {
+            self.waiting.set(true);
+        }
+        Ok(event)
     }
 }
 
-fn make_input_thread(tx: mpsc::Sender<Event>) -> thread::JoinHandle<()> {
+fn make_input_thread(
+    tx: mpsc::Sender<Event>,
+    resume: mpsc::Receiver<()>,
+) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let stdin = io::stdin();
-        for key in stdin.keys() {
-            tx.send(Event::Key(key.unwrap())).unwrap();
+        for event in stdin.events() {
+            let event = match event.unwrap() {
+                event::Event::Key(key) => Event::Key(key),
+                event::Event::Mouse(mouse) => Event::Mouse(mouse),
+                event::Event::Unsupported(_) => continue,
+            };
+            tx.send(event).unwrap();
+            if resume.recv().is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Watches the notes and taskwarrior's data, reporting changes once they
+/// settle down.
+fn make_watcher(tx: mpsc::Sender<Event>, watch: &Watch) -> Result<RecommendedWatcher> {
+    let (changes, changes_rx) = mpsc::channel();
+    let tasks = watch.tasks.clone();
+    let notes = watch.notes.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let event = match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => event,
+            _ => return,
+        };
+        for path in &event.paths {
+            let changed = match &tasks {
+                Some(tasks) if path.starts_with(tasks) => Changed::Tasks,
+                _ if is_hidden(path, &notes) => continue,
+                _ => Changed::Notes,
+            };
+            // the receiver is only gone once taskn is exiting
+            let _ = changes.send(changed);
         }
     })
+    .context("error watching for changes")?;
+
+    if let Some(tasks) = &watch.tasks {
+        watcher
+            .watch(tasks, RecursiveMode::NonRecursive)
+            .with_context(|| format!("error watching '{}' for changes", tasks.display()))?;
+    }
+    watcher
+        .watch(&watch.notes, RecursiveMode::Recursive)
+        .with_context(|| format!("error watching '{}' for changes", watch.notes.display()))?;
+
+    thread::spawn(move || {
+        while let Ok(changed) = changes_rx.recv() {
+            let mut changed = changed;
+            while let Ok(more) = changes_rx.recv_timeout(SETTLE) {
+                changed = changed.max(more);
+            }
+            if tx.send(Event::Changed(changed)).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(watcher)
+}
+
+/// Whether a path inside of the notes is hidden, like the git repository or
+/// the files taskn keeps for itself, which are not notes.
+fn is_hidden(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|component| match component {
+            Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+            _ => false,
+        })
 }
 
 fn make_signal_thread(tx: mpsc::Sender<Event>) -> thread::JoinHandle<()> {
     thread::spawn(move || {
-        let mut signals = Signals::new(&[SIGWINCH]).unwrap();
+        let mut signals = Signals::new([SIGWINCH]).unwrap();
         loop {
             for signal in &mut signals {
                 if signal == SIGWINCH {
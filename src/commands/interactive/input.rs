@@ -0,0 +1,337 @@
+use termion::event::Key;
+use tui::{
+    style::{Modifier, Style},
+    text::{Span, Spans},
+};
+
+/// A single line of text being typed into a prompt, along with the position of
+/// the cursor inside of it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LineInput {
+    text:   String,
+    /// The cursor position, counted in characters
+    cursor: usize,
+}
+
+impl LineInput {
+    pub(crate) fn new(text: &str) -> Self {
+        let mut input = Self::default();
+        input.set(text);
+        input
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text of the input, moving the cursor to its end.
+    pub(crate) fn set(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.chars().count();
+    }
+
+    fn byte_offset(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(offset, _)| offset)
+    }
+
+    /// Applies a key which edits the line. Returns whether the key was used.
+    pub(crate) fn handle(&mut self, key: Key) -> bool {
+        let len = self.text.chars().count();
+        match key {
+            Key::Char(c) if c != '\n' && c != '\t' => {
+                let offset = self.byte_offset(self.cursor);
+                self.text.insert(offset, c);
+                self.cursor += 1;
+            },
+            Key::Backspace =>
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    let offset = self.byte_offset(self.cursor);
+                    self.text.remove(offset);
+                },
+            Key::Delete | Key::Ctrl('d') =>
+                if self.cursor < len {
+                    let offset = self.byte_offset(self.cursor);
+                    self.text.remove(offset);
+                },
+            Key::Left | Key::Ctrl('b') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right | Key::Ctrl('f') => self.cursor = (self.cursor + 1).min(len),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = len,
+            Key::Ctrl('u') => {
+                let offset = self.byte_offset(self.cursor);
+                self.text.replace_range(..offset, "");
+                self.cursor = 0;
+            },
+            Key::Ctrl('k') => {
+                let offset = self.byte_offset(self.cursor);
+                self.text.truncate(offset);
+            },
+            Key::Ctrl('w') => {
+                // delete the word before the cursor, along with any whitespace after it
+                let chars = self.text.chars().collect::<Vec<_>>();
+                let mut start = self.cursor;
+                while start > 0 && chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                while start > 0 && !chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                let (from, to) = (self.byte_offset(start), self.byte_offset(self.cursor));
+                self.text.replace_range(from..to, "");
+                self.cursor = start;
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Renders the line with the cursor drawn as a reversed character.
+    pub(crate) fn spans(&self) -> Spans<'static> {
+        let offset = self.byte_offset(self.cursor);
+        let (before, rest) = self.text.split_at(offset);
+        let mut rest = rest.chars();
+        let under_cursor = rest.next().map_or_else(|| " ".to_string(), String::from);
+        Spans::from(vec![
+            Span::raw(before.to_string()),
+            Span::styled(
+                under_cursor,
+                Style::default().add_modifier(Modifier::REVERSED),
+            ),
+            Span::raw(rest.collect::<String>()),
+        ])
+    }
+}
+
+/// The candidates for completing the word being typed, stepped through with
+/// repeated presses of `Tab`
+#[derive(Clone, Debug)]
+pub(crate) struct Completion {
+    /// The text of the line before the completed word
+    before:                String,
+    pub(crate) candidates: Vec<String>,
+    index:                 usize,
+}
+
+impl Completion {
+    /// Completes a word with the known words starting with it. Returns nothing
+    /// when there aren't any.
+    pub(crate) fn new(before: &str, word: &str, known: &[String]) -> Option<Self> {
+        let candidates = known
+            .iter()
+            .filter(|candidate| candidate.starts_with(word) && *candidate != word)
+            .cloned()
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Self {
+            before: before.to_string(),
+            candidates,
+            index: 0,
+        })
+    }
+
+    /// Moves on to the next candidate, starting over after the last one.
+    pub(crate) fn next(&mut self) {
+        self.index = (self.index + 1) % self.candidates.len();
+    }
+
+    /// Whether the current candidate is the last one.
+    pub(crate) fn is_last(&self) -> bool {
+        self.index + 1 == self.candidates.len()
+    }
+
+    /// The line with the word replaced by the current candidate.
+    pub(crate) fn line(&self) -> String {
+        format!("{}{}", self.before, self.candidates[self.index])
+    }
+}
+
+/// Previously entered lines of a prompt, which can be stepped through with the
+/// arrow keys.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct History {
+    entries:  Vec<String>,
+    /// The entry currently shown, when stepping through history
+    position: Option<usize>,
+    /// What was typed before stepping into history
+    draft:    String,
+}
+
+impl History {
+    /// Records a line, moving it to the front of history if it was already
+    /// there.
+    pub(crate) fn push(&mut self, line: &str) {
+        self.position = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != line);
+        self.entries.push(line.to_string());
+    }
+
+    /// Stops stepping through history, e.g. because the prompt was closed.
+    pub(crate) fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// Applies `Up` and `Down` to the input, stepping to older and newer
+    /// entries. Returns whether the key was used.
+    pub(crate) fn handle(&mut self, key: Key, input: &mut LineInput) -> bool {
+        match key {
+            Key::Up => {
+                let position = match self.position {
+                    None if self.entries.is_empty() => return true,
+                    None => {
+                        self.draft = input.text().to_string();
+                        self.entries.len() - 1
+                    },
+                    Some(position) => position.saturating_sub(1),
+                };
+                self.position = Some(position);
+                input.set(&self.entries[position]);
+            },
+            Key::Down => match self.position {
+                None => {},
+                Some(position) if position + 1 < self.entries.len() => {
+                    self.position = Some(position + 1);
+                    input.set(&self.entries[position + 1]);
+                },
+                Some(_) => {
+                    self.position = None;
+                    input.set(&self.draft);
+                },
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut LineInput, keys: &[Key]) {
+        for key in keys {
+            assert!(input.handle(*key));
+        }
+    }
+
+    fn contents(spans: &Spans<'_>) -> Vec<String> {
+        spans.0.iter().map(|span| span.content.to_string()).collect()
+    }
+
+    #[test]
+    fn test_line_input_edits() {
+        let mut input = LineInput::new("héllo");
+        press(&mut input, &[Key::Left, Key::Left, Key::Backspace, Key::Char('L')]);
+        assert_eq!(input.text(), "héLlo");
+        press(&mut input, &[Key::Home, Key::Delete, Key::Ctrl('e'), Key::Ctrl('d')]);
+        assert_eq!(input.text(), "éLlo");
+        press(&mut input, &[Key::Ctrl('a'), Key::Backspace, Key::Right, Key::Ctrl('f')]);
+        press(&mut input, &[Key::Char('-')]);
+        assert_eq!(input.text(), "éL-lo");
+        assert!(!input.handle(Key::Char('\n')));
+        assert!(!input.handle(Key::Char('\t')));
+    }
+
+    #[test]
+    fn test_line_input_deletes_words_and_ends() {
+        let mut input = LineInput::new("one two  ");
+        press(&mut input, &[Key::Ctrl('w')]);
+        assert_eq!(input.text(), "one ");
+
+        let mut input = LineInput::new("one two three");
+        press(&mut input, &[Key::Home, Key::Right, Key::Right, Key::Right, Key::Ctrl('k')]);
+        assert_eq!(input.text(), "one");
+
+        let mut input = LineInput::new("one two");
+        press(&mut input, &[Key::Left, Key::Left, Key::Left, Key::Ctrl('u')]);
+        assert_eq!(input.text(), "two");
+        press(&mut input, &[Key::Ctrl('w'), Key::Char('x')]);
+        assert_eq!(input.text(), "xtwo");
+    }
+
+    #[test]
+    fn test_line_input_spans() {
+        let mut input = LineInput::new("ab");
+        assert_eq!(contents(&input.spans()), vec!["ab", " ", ""]);
+        press(&mut input, &[Key::Home]);
+        assert_eq!(contents(&input.spans()), vec!["", "a", "b"]);
+        assert_eq!(
+            input.spans().0[1].style,
+            Style::default().add_modifier(Modifier::REVERSED)
+        );
+    }
+
+    #[test]
+    fn test_completion() {
+        let known = ["work", "work.api", "home"].map(String::from);
+        let mut completion = Completion::new("project:", "wo", &known).unwrap();
+        assert_eq!(completion.candidates, vec!["work", "work.api"]);
+        assert_eq!(completion.line(), "project:work");
+        assert!(!completion.is_last());
+        completion.next();
+        assert_eq!(completion.line(), "project:work.api");
+        assert!(completion.is_last());
+        // the candidates start over after the last one
+        completion.next();
+        assert_eq!(completion.line(), "project:work");
+    }
+
+    #[test]
+    fn test_completion_without_candidates() {
+        let known = ["work".to_string()];
+        assert!(Completion::new("", "x", &known).is_none());
+        // a word which is already complete has nothing left to complete
+        assert!(Completion::new("", "work", &known).is_none());
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = History::default();
+        let mut input = LineInput::new("draft");
+        // there's nothing to step to yet
+        assert!(history.handle(Key::Up, &mut input));
+        assert_eq!(input.text(), "draft");
+
+        for line in ["a", "b", " ", "a"] {
+            history.push(line);
+        }
+        history.handle(Key::Up, &mut input);
+        assert_eq!(input.text(), "a");
+        history.handle(Key::Up, &mut input);
+        assert_eq!(input.text(), "b");
+        history.handle(Key::Up, &mut input);
+        assert_eq!(input.text(), "b");
+        history.handle(Key::Down, &mut input);
+        assert_eq!(input.text(), "a");
+        // stepping past the newest entry goes back to what was typed
+        history.handle(Key::Down, &mut input);
+        assert_eq!(input.text(), "draft");
+        history.handle(Key::Down, &mut input);
+        assert_eq!(input.text(), "draft");
+        assert!(!history.handle(Key::Left, &mut input));
+    }
+
+    #[test]
+    fn test_history_reset() {
+        let mut history = History::default();
+        history.push("old");
+        history.push("new");
+        let mut input = LineInput::default();
+        history.handle(Key::Up, &mut input);
+        history.handle(Key::Up, &mut input);
+        history.reset();
+        input.set("typed");
+        history.handle(Key::Up, &mut input);
+        assert_eq!(input.text(), "new");
+        history.handle(Key::Down, &mut input);
+        assert_eq!(input.text(), "typed");
+    }
+}
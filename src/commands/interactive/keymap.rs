@@ -0,0 +1,697 @@
+use std::{collections::BTreeMap, convert::TryFrom, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use termion::event::Key;
+use tui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+};
+
+use super::{
+    common_render, preview::popup, ActionResult, CommonState, Frame, Mode, Normal, Preview,
+};
+use crate::{config, opt::Opt};
+
+/// The modes which read keys through the keymap, each with its own bindings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Scope {
+    Normal,
+    Preview,
+    Shift,
+}
+
+impl Scope {
+    pub(crate) const ALL: [Self; 3] = [Self::Normal, Self::Preview, Self::Shift];
+
+    /// The name of the scope, as in `[interactive.keys.<name>]`
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Preview => "preview",
+            Self::Shift => "shift",
+        }
+    }
+
+    fn defaults(self) -> &'static [(&'static str, Command)] {
+        match self {
+            Self::Normal => &[
+                ("k", Command::Up),
+                ("<Up>", Command::Up),
+                ("K", Command::Up),
+                ("j", Command::Down),
+                ("<Down>", Command::Down),
+                ("J", Command::Down),
+                ("gg", Command::Top),
+                ("G", Command::Bottom),
+                ("t", Command::ToggleTree),
+                ("h", Command::Collapse),
+                ("<Left>", Command::Collapse),
+                ("l", Command::Expand),
+                ("<Right>", Command::Expand),
+                ("f", Command::Filter),
+                ("/", Command::Search),
+                ("n", Command::NextMatch),
+                ("N", Command::PreviousMatch),
+                ("D", Command::ToggleDetail),
+                ("o", Command::Add),
+                ("O", Command::AddAndOpen),
+                ("u", Command::Undo),
+                ("<C-r>", Command::Redo),
+                ("<Space>", Command::Mark),
+                ("V", Command::MarkRange),
+                ("U", Command::ClearMarks),
+                ("d", Command::Done),
+                ("<Del>", Command::Delete),
+                ("+", Command::AddTag),
+                ("-", Command::RemoveTag),
+                ("p", Command::SetProject),
+                ("w", Command::SetWait),
+                ("e", Command::Open),
+                ("<Enter>", Command::Open),
+                ("i", Command::EditNote),
+                ("<Tab>", Command::FocusPreview),
+                ("s", Command::Shift),
+                ("m", Command::Modify),
+                ("X", Command::TaskEdit),
+                ("a", Command::Annotate),
+                ("A", Command::ManageAnnotations),
+                (":", Command::Run),
+                ("?", Command::Help),
+                ("q", Command::Quit),
+            ],
+            Self::Preview => &[
+                ("j", Command::Down),
+                ("<Down>", Command::Down),
+                ("k", Command::Up),
+                ("<Up>", Command::Up),
+                ("<C-d>", Command::HalfPageDown),
+                ("<C-u>", Command::HalfPageUp),
+                ("<Space>", Command::PageDown),
+                ("<PageDown>", Command::PageDown),
+                ("<C-f>", Command::PageDown),
+                ("<PageUp>", Command::PageUp),
+                ("<C-b>", Command::PageUp),
+                ("gg", Command::Top),
+                ("<Home>", Command::Top),
+                ("G", Command::Bottom),
+                ("<End>", Command::Bottom),
+                ("o", Command::Outline),
+                ("<Tab>", Command::Back),
+                ("h", Command::Back),
+                ("<Left>", Command::Back),
+                ("<Esc>", Command::Back),
+                ("?", Command::Help),
+                ("q", Command::Quit),
+            ],
+            Self::Shift => &[
+                ("k", Command::Up),
+                ("<Up>", Command::Up),
+                ("K", Command::Up),
+                ("j", Command::Down),
+                ("<Down>", Command::Down),
+                ("J", Command::Down),
+                ("s", Command::Save),
+                ("<Enter>", Command::Save),
+                ("<Esc>", Command::Cancel),
+                ("<C-f>", Command::Cancel),
+            ],
+        }
+    }
+
+    /// Whether a command does anything in the scope, which is the case when
+    /// it's bound by default.
+    fn allows(self, command: Command) -> bool {
+        self.defaults().iter().any(|(_, other)| *other == command)
+    }
+}
+
+/// The actions which keys can be bound to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Command {
+    Up,
+    Down,
+    Top,
+    Bottom,
+    ToggleTree,
+    Collapse,
+    Expand,
+    Filter,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ToggleDetail,
+    Add,
+    AddAndOpen,
+    Undo,
+    Redo,
+    Mark,
+    MarkRange,
+    ClearMarks,
+    Done,
+    Delete,
+    AddTag,
+    RemoveTag,
+    SetProject,
+    SetWait,
+    Open,
+    EditNote,
+    FocusPreview,
+    Shift,
+    Modify,
+    TaskEdit,
+    Annotate,
+    ManageAnnotations,
+    Run,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Outline,
+    Back,
+    Save,
+    Cancel,
+    Help,
+    Quit,
+}
+
+/// The name of each command, as written in the config file, and what it does
+const COMMANDS: &[(Command, &str, &str)] = &[
+    (Command::Up, "up", "move up"),
+    (Command::Down, "down", "move down"),
+    (Command::Top, "top", "go to the top, or to row N"),
+    (Command::Bottom, "bottom", "go to the bottom, or to row N"),
+    (Command::ToggleTree, "toggle-tree", "group tasks by project"),
+    (Command::Collapse, "collapse", "collapse a project"),
+    (Command::Expand, "expand", "expand a project"),
+    (Command::Filter, "filter", "change the filter"),
+    (Command::Search, "search", "search tasks"),
+    (Command::NextMatch, "next-match", "jump to the next match"),
+    (Command::PreviousMatch, "previous-match", "jump to the previous match"),
+    (Command::ToggleDetail, "toggle-detail", "show or hide the detail pane"),
+    (Command::Add, "add", "add a task"),
+    (Command::AddAndOpen, "add-and-open", "add a task and open its note"),
+    (Command::Undo, "undo", "undo the last change"),
+    (Command::Redo, "redo", "redo the last undone change"),
+    (Command::Mark, "mark", "mark the task"),
+    (Command::MarkRange, "mark-range", "start or finish marking a range"),
+    (Command::ClearMarks, "clear-marks", "clear the marks"),
+    (Command::Done, "done", "mark tasks done"),
+    (Command::Delete, "delete", "delete tasks"),
+    (Command::AddTag, "add-tag", "add a tag to tasks"),
+    (Command::RemoveTag, "remove-tag", "remove a tag from tasks"),
+    (Command::SetProject, "set-project", "set the project of tasks"),
+    (Command::SetWait, "set-wait", "hide tasks until a date"),
+    (Command::Open, "open", "open notes in the editor"),
+    (Command::EditNote, "edit-note", "edit the note in the preview"),
+    (Command::FocusPreview, "focus-preview", "focus the preview"),
+    (Command::Shift, "shift", "move tasks in the manual order"),
+    (Command::Modify, "modify", "modify the task's attributes"),
+    (Command::TaskEdit, "task-edit", "run `task edit`"),
+    (Command::Annotate, "annotate", "annotate the task"),
+    (Command::ManageAnnotations, "annotations", "delete annotations or move them to the note"),
+    (Command::Run, "run", "run a taskwarrior command on tasks"),
+    (Command::HalfPageDown, "half-page-down", "scroll down half a page"),
+    (Command::HalfPageUp, "half-page-up", "scroll up half a page"),
+    (Command::PageDown, "page-down", "scroll down a page"),
+    (Command::PageUp, "page-up", "scroll up a page"),
+    (Command::Outline, "outline", "jump to a heading"),
+    (Command::Back, "back", "go back to the task list"),
+    (Command::Save, "save", "save the new order"),
+    (Command::Cancel, "cancel", "put the tasks back"),
+    (Command::Help, "help", "show the keys"),
+    (Command::Quit, "quit", "quit taskn"),
+];
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        COMMANDS
+            .iter()
+            .find(|(_, name, _)| *name == s.trim())
+            .map(|(command, ..)| *command)
+            .ok_or_else(|| format!("unknown command '{}'", s))
+    }
+}
+
+impl Command {
+    pub(crate) fn name(self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(command, ..)| *command == self)
+            .map_or("", |(_, name, _)| name)
+    }
+
+    fn description(self) -> &'static str {
+        COMMANDS
+            .iter()
+            .find(|(command, ..)| *command == self)
+            .map_or("", |(.., description)| description)
+    }
+}
+
+/// The key sequences bound to commands in each scope, along with the keys
+/// typed so far which start one of them
+#[derive(Clone, Debug)]
+pub(crate) struct Keymap {
+    bindings: Vec<(Scope, Vec<Key>, Command)>,
+    /// The count typed before a command, e.g. the `5` of `5j`
+    count:    Option<usize>,
+    pending:  Vec<Key>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        from_config(&config::Keys::default()).expect("the default keys are valid")
+    }
+}
+
+impl Keymap {
+    /// Reads a key, returning the command it finishes along with the count
+    /// typed before it. Keys which don't lead to a command are dropped.
+    pub(crate) fn read(&mut self, scope: Scope, key: Key) -> Option<(Command, Option<usize>)> {
+        if let Key::Char(digit @ '0'..='9') = key {
+            let digit = digit.to_digit(10).and_then(|digit| usize::try_from(digit).ok());
+            if let Some(digit) = digit.filter(|digit| *digit > 0 || self.count.is_some()) {
+                if self.pending.is_empty() {
+                    let count = self.count.unwrap_or(0).saturating_mul(10);
+                    self.count = Some(count.saturating_add(digit));
+                    return None;
+                }
+            }
+        }
+
+        self.pending.push(key);
+        let mut prefix = false;
+        for (other, keys, command) in &self.bindings {
+            if *other != scope || !keys.starts_with(&self.pending) {
+                continue;
+            }
+            if keys.len() == self.pending.len() {
+                let command = *command;
+                let count = self.count.take();
+                self.pending.clear();
+                return Some((command, count));
+            }
+            prefix = true;
+        }
+        if !prefix {
+            self.count = None;
+            self.pending.clear();
+        }
+        None
+    }
+
+    /// The keys bound to each command in a scope, e.g. `j, <Down>` for moving
+    /// down, along with what the command does.
+    pub(crate) fn help(&self, scope: Scope) -> Vec<(String, &'static str)> {
+        COMMANDS
+            .iter()
+            .filter_map(|(command, ..)| {
+                let keys = self
+                    .bindings
+                    .iter()
+                    .filter(|(other, _, bound)| *other == scope && bound == command)
+                    .map(|(_, keys, _)| show_keys(keys))
+                    .collect::<Vec<_>>();
+                (!keys.is_empty()).then(|| (keys.join(", "), command.description()))
+            })
+            .collect()
+    }
+}
+
+/// Reads the keys bound in the config on top of the default ones. Bindings
+/// which can't be told apart, like `g` and `gg`, are reported together.
+pub(crate) fn from_config(config: &config::Keys) -> Result<Keymap> {
+    let mut bindings = vec![];
+    let mut problems = vec![];
+    for scope in Scope::ALL {
+        let configured = match scope {
+            Scope::Normal => &config.normal,
+            Scope::Preview => &config.preview,
+            Scope::Shift => &config.shift,
+        };
+        let mut scoped = bindings_of(scope, configured, &mut problems);
+        for (keys, command) in scope.defaults() {
+            let keys = parse_keys(keys).map_err(|e| anyhow!(e))?;
+            if !scoped.iter().any(|(other, _)| *other == keys) {
+                scoped.push((keys, Some(*command)));
+            }
+        }
+        let scoped = scoped
+            .into_iter()
+            .filter_map(|(keys, command)| Some((keys, command?)))
+            .collect::<Vec<_>>();
+
+        for (i, (keys, _)) in scoped.iter().enumerate() {
+            if let Some(Key::Char('1'..='9')) = keys.first() {
+                problems.push(format!(
+                    "'{}' in [interactive.keys.{}] starts with a count",
+                    show_keys(keys),
+                    scope.name()
+                ));
+            }
+            for (other, _) in &scoped[i + 1..] {
+                if keys.starts_with(other) || other.starts_with(keys) {
+                    problems.push(format!(
+                        "'{}' and '{}' conflict in [interactive.keys.{}]",
+                        show_keys(keys),
+                        show_keys(other),
+                        scope.name()
+                    ));
+                }
+            }
+        }
+        bindings.extend(
+            scoped
+                .into_iter()
+                .map(|(keys, command)| (scope, keys, command)),
+        );
+    }
+    if !problems.is_empty() {
+        return Err(anyhow!("invalid keys in config: {}", problems.join(", ")));
+    }
+    Ok(Keymap {
+        bindings,
+        count: None,
+        pending: vec![],
+    })
+}
+
+/// Parses the bindings of a scope from the config. A command of `none`
+/// removes the default binding of the keys.
+fn bindings_of(
+    scope: Scope,
+    configured: &BTreeMap<String, String>,
+    problems: &mut Vec<String>,
+) -> Vec<(Vec<Key>, Option<Command>)> {
+    let mut bindings = vec![];
+    for (keys, command) in configured {
+        let parsed = parse_keys(keys).and_then(|keys| {
+            if command.trim() == "none" {
+                return Ok((keys, None));
+            }
+            let command = command.parse::<Command>()?;
+            if scope.allows(command) {
+                Ok((keys, Some(command)))
+            } else {
+                Err(format!("'{}' can't be used in {}", command.name(), scope.name()))
+            }
+        });
+        match parsed {
+            Ok((keys, command)) =>
+                if bindings.iter().any(|(other, _)| *other == keys) {
+                    problems.push(format!(
+                        "'{}' is bound twice in [interactive.keys.{}]",
+                        show_keys(&keys),
+                        scope.name()
+                    ));
+                } else {
+                    bindings.push((keys, command));
+                },
+            Err(e) => problems.push(e),
+        }
+    }
+    bindings
+}
+
+/// Keys which are written with a name between `<` and `>`
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("Enter", Key::Char('\n')),
+    ("CR", Key::Char('\n')),
+    ("Tab", Key::Char('\t')),
+    ("S-Tab", Key::BackTab),
+    ("Space", Key::Char(' ')),
+    ("Esc", Key::Esc),
+    ("BS", Key::Backspace),
+    ("Del", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("lt", Key::Char('<')),
+];
+
+/// Parses a sequence of keys like `gg`, `<C-r>` or `<Space>`. Special keys
+/// are named between `<` and `>`, where `<lt>` is `<` itself.
+pub(crate) fn parse_keys(s: &str) -> Result<Vec<Key>, String> {
+    let mut keys = vec![];
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let name = rest
+            .strip_prefix('<')
+            .and_then(|after| after.split_once('>'))
+            .filter(|(name, _)| !name.is_empty());
+        match name {
+            Some((name, after)) => {
+                keys.push(parse_named(name).ok_or_else(|| format!("unknown key '<{}>'", name))?);
+                rest = after;
+            },
+            None => {
+                keys.push(Key::Char(c));
+                rest = &rest[c.len_utf8()..];
+            },
+        }
+    }
+    if keys.is_empty() {
+        return Err("a key is required".to_string());
+    }
+    Ok(keys)
+}
+
+fn parse_named(name: &str) -> Option<Key> {
+    if let Some((_, key)) = NAMED_KEYS
+        .iter()
+        .find(|(other, _)| other.eq_ignore_ascii_case(name))
+    {
+        return Some(*key);
+    }
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    match name.split_once('-') {
+        // terminals send these the same as the keys they stand for
+        Some(("C" | "c", "j" | "J" | "m" | "M")) => Some(Key::Char('\n')),
+        Some(("C" | "c", "i" | "I")) => Some(Key::Char('\t')),
+        Some(("C" | "c", "[")) => Some(Key::Esc),
+        Some(("C" | "c", c)) => single(c).map(|c| Key::Ctrl(c.to_ascii_lowercase())),
+        Some(("A" | "a" | "M" | "m", c)) => single(c).map(Key::Alt),
+        _ => name
+            .strip_prefix(['F', 'f'])
+            .and_then(|n| n.parse().ok())
+            .map(Key::F),
+    }
+}
+
+/// Writes keys the way they're parsed, e.g. `<C-r>`.
+pub(crate) fn show_keys(keys: &[Key]) -> String {
+    keys.iter()
+        .map(|key| {
+            if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, other)| other == key) {
+                return format!("<{}>", name);
+            }
+            match key {
+                Key::Char(c) => c.to_string(),
+                Key::Ctrl(c) => format!("<C-{}>", c),
+                Key::Alt(c) => format!("<A-{}>", c),
+                Key::F(n) => format!("<F{}>", n),
+                key => format!("{:?}", key),
+            }
+        })
+        .collect()
+}
+
+/// Lists the keys bound in each mode, generated from the keymap.
+pub(crate) struct Help {
+    /// The mode to go back to once the help is closed
+    back:   Scope,
+    scroll: u16,
+}
+
+impl Help {
+    pub(crate) fn new(back: Scope) -> Self {
+        Self { back, scroll: 0 }
+    }
+}
+
+impl Mode for Help {
+    fn name(&self) -> &'static str {
+        "HELP"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        common_render(frame, common_state, &[Modifier::DIM], area);
+
+        let mut lines = vec![];
+        for scope in Scope::ALL {
+            if !lines.is_empty() {
+                lines.push(Spans::default());
+            }
+            lines.push(Spans::from(Span::styled(scope.name(), common_state.theme.label)));
+            lines.extend(
+                common_state
+                    .keymap
+                    .help(scope)
+                    .into_iter()
+                    .map(|(keys, description)| {
+                        Spans::from(format!("  {:<24} {}", keys, description))
+                    }),
+            );
+        }
+        let area = popup(area, area.height.saturating_sub(4));
+        let paragraph = Paragraph::new(lines).scroll((self.scroll, 0)).block(
+            common_state
+                .theme
+                .block()
+                .title("Keys (j/k to scroll, any other key to close)"),
+        );
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        _common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Char('j') | Key::Down => self.scroll = self.scroll.saturating_add(1),
+            Key::Char('k') | Key::Up => self.scroll = self.scroll.saturating_sub(1),
+            _ => {
+                let back: Box<dyn Mode> = match self.back {
+                    Scope::Preview => Box::new(Preview),
+                    Scope::Normal | Scope::Shift => Box::new(Normal),
+                };
+                return Ok(ActionResult {
+                    new_mode: Some(back),
+                    ..ActionResult::default()
+                });
+            },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normal(bindings: &[(&str, &str)]) -> config::Keys {
+        config::Keys {
+            normal: bindings
+                .iter()
+                .map(|(keys, command)| (keys.to_string(), command.to_string()))
+                .collect(),
+            ..config::Keys::default()
+        }
+    }
+
+    fn read_all(
+        keymap: &mut Keymap,
+        scope: Scope,
+        keys: &str,
+    ) -> Option<(Command, Option<usize>)> {
+        parse_keys(keys)
+            .unwrap()
+            .into_iter()
+            .fold(None, |_, key| keymap.read(scope, key))
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(parse_keys("gg"), Ok(vec![Key::Char('g'), Key::Char('g')]));
+        assert_eq!(parse_keys("<C-r>"), Ok(vec![Key::Ctrl('r')]));
+        assert_eq!(parse_keys("<c-R>"), Ok(vec![Key::Ctrl('r')]));
+        assert_eq!(parse_keys("<space>"), Ok(vec![Key::Char(' ')]));
+        assert_eq!(parse_keys("<A-x><F5>"), Ok(vec![Key::Alt('x'), Key::F(5)]));
+        assert_eq!(parse_keys("<C-m>"), Ok(vec![Key::Char('\n')]));
+        assert_eq!(parse_keys("é"), Ok(vec![Key::Char('é')]));
+    }
+
+    #[test]
+    fn test_parse_keys_with_angle_brackets() {
+        assert_eq!(parse_keys("<lt>x"), Ok(vec![Key::Char('<'), Key::Char('x')]));
+        assert_eq!(parse_keys("<"), Ok(vec![Key::Char('<')]));
+        assert_eq!(parse_keys("<>"), Ok(vec![Key::Char('<'), Key::Char('>')]));
+        assert_eq!(parse_keys("<Bogus>"), Err("unknown key '<Bogus>'".to_string()));
+        assert!(parse_keys("").is_err());
+    }
+
+    #[test]
+    fn test_show_keys() {
+        assert_eq!(show_keys(&[Key::Ctrl('r'), Key::Char('\n')]), "<C-r><Enter>");
+        for scope in Scope::ALL {
+            for (keys, _) in scope.defaults() {
+                let parsed = parse_keys(keys).unwrap();
+                assert_eq!(parse_keys(&show_keys(&parsed)), Ok(parsed));
+            }
+        }
+    }
+
+    #[test]
+    fn test_command_names() {
+        for (command, name, _) in COMMANDS {
+            assert_eq!(name.parse(), Ok(*command));
+            assert_eq!(command.name(), *name);
+        }
+        assert!("bogus".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        let mut keymap = Keymap::default();
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "j"), Some((Command::Down, None)));
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "g"), None);
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "g"), Some((Command::Top, None)));
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "s"), Some((Command::Shift, None)));
+        assert_eq!(read_all(&mut keymap, Scope::Shift, "s"), Some((Command::Save, None)));
+    }
+
+    #[test]
+    fn test_read_counts() {
+        let mut keymap = Keymap::default();
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "5j"), Some((Command::Down, Some(5))));
+        assert_eq!(
+            read_all(&mut keymap, Scope::Normal, "10G"),
+            Some((Command::Bottom, Some(10)))
+        );
+        // a count can't start with a zero, and is dropped along with unbound keys
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "0j"), Some((Command::Down, None)));
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "3zj"), Some((Command::Down, None)));
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "gxj"), Some((Command::Down, None)));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let mut keymap = from_config(&normal(&[("x", "done"), ("d", "none")])).unwrap();
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "x"), Some((Command::Done, None)));
+        assert_eq!(read_all(&mut keymap, Scope::Normal, "d"), None);
+        let help = keymap.help(Scope::Normal);
+        assert!(help.contains(&("x".to_string(), "mark tasks done")));
+        assert!(help.contains(&("j, <Down>, J".to_string(), "move down")));
+    }
+
+    #[test]
+    fn test_from_config_problems() {
+        let problems = |bindings| from_config(&normal(bindings)).unwrap_err().to_string();
+        let conflict = "'g' and 'gg' conflict in [interactive.keys.normal]";
+        assert!(problems(&[("g", "top")]).contains(conflict));
+        let count = "'5x' in [interactive.keys.normal] starts with a count";
+        assert!(problems(&[("5x", "done")]).contains(count));
+        assert!(problems(&[("x", "save")]).contains("'save' can't be used in normal"));
+        assert!(problems(&[("<Bogus>", "done")]).contains("unknown key '<Bogus>'"));
+        assert!(problems(&[("x", "bogus")]).contains("unknown command 'bogus'"));
+        // the same keys written two ways are bound twice
+        let twice = "'<Space>' is bound twice";
+        assert!(problems(&[("<Space>", "mark"), (" ", "done")]).contains(twice));
+    }
+}
@@ -0,0 +1,727 @@
+use std::sync::LazyLock;
+
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+};
+
+use super::{editor::wrap, theme::Theme};
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// How the text of a note is marked up, from the `--file-format`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Format {
+    Markdown,
+    Org,
+    /// Anything else, which is only wrapped
+    Plain,
+}
+
+impl Format {
+    pub(crate) fn from_extension(extension: &str) -> Self {
+        match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Self::Markdown,
+            "org" => Self::Org,
+            _ => Self::Plain,
+        }
+    }
+}
+
+/// A heading of a note, for jumping to it
+#[derive(Clone, Debug)]
+pub(crate) struct Heading {
+    pub(crate) level: usize,
+    pub(crate) title: String,
+    /// The rendered line the heading is on
+    pub(crate) line:  usize,
+}
+
+/// A note rendered for the preview
+pub(crate) struct Rendered {
+    pub(crate) lines:    Vec<Spans<'static>>,
+    pub(crate) headings: Vec<Heading>,
+}
+
+/// Renders a note into styled lines, wrapped to the width they are shown at.
+pub(crate) fn render(text: &str, format: Format, width: usize, theme: &Theme) -> Rendered {
+    let mut writer = Writer::new(width, theme.clone());
+    match format {
+        Format::Markdown => markdown(text, &mut writer),
+        Format::Org => org(text, &mut writer),
+        Format::Plain =>
+            for line in text.lines() {
+                writer.text(line);
+                writer.flush();
+            },
+    }
+    writer.finish()
+}
+
+/// Builds up wrapped lines, keeping track of the styles and the block
+/// prefixes (quote bars and list indentation) which apply to the text.
+struct Writer {
+    width:      usize,
+    lines:      Vec<Spans<'static>>,
+    /// The spans of the line being written
+    line:       Vec<Span<'static>>,
+    /// The styles of the text, innermost last
+    styles:     Vec<Style>,
+    /// What is put before every line of the enclosing blocks
+    prefixes:   Vec<Span<'static>>,
+    /// A list bullet, put before the next line in place of the last prefix
+    bullet:     Option<Span<'static>>,
+    /// Whether the last line written is blank, so that blocks are only ever
+    /// separated by one blank line
+    last_blank: bool,
+    headings:   Vec<Heading>,
+    theme:      Theme,
+}
+
+impl Writer {
+    fn new(width: usize, theme: Theme) -> Self {
+        Self {
+            width:      width.max(1),
+            lines:      vec![],
+            line:       vec![],
+            styles:     vec![],
+            prefixes:   vec![],
+            bullet:     None,
+            last_blank: true,
+            headings:   vec![],
+            theme,
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles
+            .iter()
+            .fold(Style::default(), |style, inner| style.patch(*inner))
+    }
+
+    fn text(&mut self, text: &str) {
+        self.styled(text, Style::default());
+    }
+
+    fn styled(&mut self, text: &str, style: Style) {
+        if !text.is_empty() {
+            let style = self.style().patch(style);
+            self.line.push(Span::styled(text.to_string(), style));
+        }
+    }
+
+    /// The number of characters the block prefixes take up.
+    fn prefix_width(&self) -> usize {
+        self.prefixes
+            .iter()
+            .map(|span| span.content.chars().count())
+            .sum()
+    }
+
+    /// Writes out the current line, if anything has been written to it.
+    fn flush(&mut self) {
+        if self.line.is_empty() && self.bullet.is_none() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.line);
+        self.write(&spans);
+    }
+
+    /// Wraps a line to the width, putting the block prefixes before each part.
+    fn write(&mut self, spans: &[Span<'static>]) {
+        let rest = self.prefixes.clone();
+        let mut first = rest.clone();
+        if let Some(bullet) = self.bullet.take() {
+            first.pop();
+            first.push(bullet);
+        }
+        let styled = spans
+            .iter()
+            .flat_map(|span| span.content.chars().map(move |c| (c, span.style)))
+            .collect::<Vec<_>>();
+        let chars = styled.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        let width = self.width.saturating_sub(self.prefix_width()).max(1);
+        for (i, (start, end)) in wrap(&chars, width).into_iter().enumerate() {
+            let mut line = if i == 0 { first.clone() } else { rest.clone() };
+            // characters with the same style are kept in one span
+            let mut text = String::new();
+            let mut style = None;
+            for (c, c_style) in &styled[start..end] {
+                if style.is_some_and(|style| style != *c_style) {
+                    line.push(Span::styled(std::mem::take(&mut text), style.unwrap_or_default()));
+                }
+                style = Some(*c_style);
+                text.push(*c);
+            }
+            if !text.is_empty() {
+                line.push(Span::styled(text, style.unwrap_or_default()));
+            }
+            self.lines.push(Spans::from(line));
+        }
+        self.last_blank = false;
+    }
+
+    /// Separates one block from the next.
+    fn blank(&mut self) {
+        self.flush();
+        if !self.last_blank {
+            self.lines.push(Spans::from(self.prefixes.clone()));
+            self.last_blank = true;
+        }
+    }
+
+    fn end_quote(&mut self) {
+        self.flush();
+        // the blank line after the quote's last paragraph isn't part of it
+        if self.last_blank && !self.lines.is_empty() {
+            self.lines.pop();
+            self.last_blank = false;
+        }
+        self.styles.pop();
+        self.prefixes.pop();
+        self.blank();
+    }
+
+    fn rule(&mut self) {
+        self.flush();
+        let width = self.width.saturating_sub(self.prefix_width());
+        self.styled(&"─".repeat(width), self.theme.faint);
+        self.flush();
+        self.blank();
+    }
+
+    /// Writes a code block, highlighting it as the language when it is known.
+    fn code_block(&mut self, language: &str, code: &str) {
+        self.flush();
+        let syntax = SYNTAXES
+            .find_syntax_by_token(language)
+            .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+        // themes without colors leave code blocks as they are
+        let mut highlighter = self
+            .theme
+            .highlighting
+            .and_then(|name| THEMES.themes.get(name))
+            .map(|theme| HighlightLines::new(syntax, theme));
+        for line in LinesWithEndings::from(code) {
+            let ranges = highlighter
+                .as_mut()
+                .map(|highlighter| highlighter.highlight_line(line, &SYNTAXES));
+            let spans = match ranges {
+                Some(Ok(ranges)) => ranges
+                    .into_iter()
+                    .map(|(style, text)| {
+                        let mut tui_style = Style::default().fg(Color::Rgb(
+                            style.foreground.r,
+                            style.foreground.g,
+                            style.foreground.b,
+                        ));
+                        if style.font_style.contains(FontStyle::BOLD) {
+                            tui_style = tui_style.add_modifier(Modifier::BOLD);
+                        }
+                        if style.font_style.contains(FontStyle::ITALIC) {
+                            tui_style = tui_style.add_modifier(Modifier::ITALIC);
+                        }
+                        Span::styled(text.trim_end_matches('\n').to_string(), tui_style)
+                    })
+                    .collect(),
+                Some(Err(_)) | None => vec![Span::raw(line.trim_end_matches('\n').to_string())],
+            };
+            self.write(&spans);
+        }
+        self.blank();
+    }
+
+    fn checkbox(&self, checked: bool) -> Span<'static> {
+        if checked {
+            Span::styled("[x] ", self.theme.checked)
+        } else {
+            Span::raw("[ ] ")
+        }
+    }
+
+    fn finish(mut self) -> Rendered {
+        self.flush();
+        while self.last_blank && !self.lines.is_empty() {
+            self.lines.pop();
+            self.last_blank = self.lines.last().is_some_and(|line| line.width() == 0);
+        }
+        Rendered {
+            lines:    self.lines,
+            headings: self.headings,
+        }
+    }
+}
+
+fn markdown(text: &str, writer: &mut Writer) {
+    let options = Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+    // the counters of the lists being written, which are none when unordered
+    let mut lists: Vec<Option<u64>> = vec![];
+    // the destination and text of the links being written
+    let mut links: Vec<(String, String)> = vec![];
+    let mut code: Option<(String, String)> = None;
+    let mut heading: Option<Heading> = None;
+
+    for event in Parser::new_ext(text, options) {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Heading(level, ..) => {
+                    writer.flush();
+                    let level = heading_level(level);
+                    heading = Some(Heading {
+                        level,
+                        title: String::new(),
+                        line: writer.lines.len(),
+                    });
+                    writer.styles.push(writer.theme.heading_style(level));
+                    writer.text(&format!("{} ", "#".repeat(level)));
+                },
+                Tag::BlockQuote => {
+                    writer.flush();
+                    writer
+                        .prefixes
+                        .push(Span::styled("│ ", writer.theme.faint));
+                    writer
+                        .styles
+                        .push(Style::default().add_modifier(Modifier::ITALIC));
+                },
+                Tag::CodeBlock(kind) => {
+                    writer.flush();
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) =>
+                            info.split_whitespace().next().unwrap_or_default().to_string(),
+                        CodeBlockKind::Indented => String::new(),
+                    };
+                    code = Some((language, String::new()));
+                },
+                Tag::List(start) => {
+                    writer.flush();
+                    lists.push(start);
+                },
+                Tag::Item => {
+                    writer.flush();
+                    let bullet = match lists.last_mut() {
+                        Some(Some(number)) => {
+                            *number += 1;
+                            format!("{}. ", *number - 1)
+                        },
+                        _ => "• ".to_string(),
+                    };
+                    writer
+                        .prefixes
+                        .push(Span::raw(" ".repeat(bullet.chars().count())));
+                    writer.bullet = Some(Span::raw(bullet));
+                },
+                Tag::Emphasis => writer
+                    .styles
+                    .push(Style::default().add_modifier(Modifier::ITALIC)),
+                Tag::Strong => writer
+                    .styles
+                    .push(Style::default().add_modifier(Modifier::BOLD)),
+                Tag::Strikethrough => writer
+                    .styles
+                    .push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+                Tag::Link(_, destination, _) | Tag::Image(_, destination, _) => {
+                    writer.styles.push(writer.theme.link);
+                    links.push((destination.to_string(), String::new()));
+                },
+                _ => {},
+            },
+            Event::End(tag) => match tag {
+                Tag::Paragraph => {
+                    // items of tight lists are kept together
+                    if lists.is_empty() {
+                        writer.blank();
+                    } else {
+                        writer.flush();
+                    }
+                },
+                Tag::Heading(..) => {
+                    writer.headings.extend(heading.take());
+                    writer.styles.pop();
+                    writer.blank();
+                },
+                Tag::BlockQuote => writer.end_quote(),
+                Tag::CodeBlock(_) =>
+                    if let Some((language, text)) = code.take() {
+                        writer.code_block(&language, &text);
+                    },
+                Tag::List(_) => {
+                    lists.pop();
+                    if lists.is_empty() {
+                        writer.blank();
+                    }
+                },
+                Tag::Item => {
+                    writer.flush();
+                    writer.prefixes.pop();
+                },
+                Tag::Emphasis | Tag::Strong | Tag::Strikethrough => {
+                    writer.styles.pop();
+                },
+                Tag::Link(..) | Tag::Image(..) => {
+                    writer.styles.pop();
+                    if let Some((destination, text)) = links.pop() {
+                        if destination != text {
+                            writer.styled(
+                                &format!(" ({})", destination),
+                                writer.theme.faint,
+                            );
+                        }
+                    }
+                },
+                _ => {},
+            },
+            Event::Text(text) => match &mut code {
+                Some((_, code)) => code.push_str(&text),
+                None => {
+                    if let Some((_, link_text)) = links.last_mut() {
+                        link_text.push_str(&text);
+                    }
+                    if let Some(heading) = &mut heading {
+                        heading.title.push_str(&text);
+                    }
+                    writer.text(&text);
+                },
+            },
+            Event::Code(text) => {
+                if let Some(heading) = &mut heading {
+                    heading.title.push_str(&text);
+                }
+                writer.styled(&text, writer.theme.code);
+            },
+            Event::Html(html) => writer.styled(
+                html.trim_end_matches('\n'),
+                writer.theme.faint,
+            ),
+            Event::SoftBreak => writer.text(" "),
+            Event::HardBreak => writer.flush(),
+            Event::Rule => writer.rule(),
+            Event::TaskListMarker(checked) => {
+                // the checkbox takes the place of the bullet
+                writer.bullet = Some(writer.checkbox(checked));
+                writer.prefixes.pop();
+                writer.prefixes.push(Span::raw("    "));
+            },
+            Event::FootnoteReference(name) => writer.text(&format!("[^{}]", name)),
+        }
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Renders org mode, line by line. Only the parts of org which are common in
+/// notes are understood: headings, lists, checkboxes, source and quote
+/// blocks, fixed width lines and inline markup.
+fn org(text: &str, writer: &mut Writer) {
+    let mut code: Option<(String, String)> = None;
+    let mut in_list = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let keyword = trimmed.to_ascii_lowercase();
+
+        if let Some((language, code_text)) = &mut code {
+            if keyword.starts_with("#+end_src") || keyword.starts_with("#+end_example") {
+                let language = language.clone();
+                let code_text = std::mem::take(code_text);
+                writer.code_block(&language, &code_text);
+                code = None;
+            } else {
+                code_text.push_str(line);
+                code_text.push('\n');
+            }
+            continue;
+        }
+
+        // a list ends at a blank line, or at a line which isn't indented under
+        // its items
+        let list_item = org_list_item(line);
+        if in_list && (trimmed.is_empty() || (list_item.is_none() && trimmed == line.trim_end()))
+        {
+            writer.flush();
+            writer.prefixes.pop();
+            in_list = false;
+        }
+        if trimmed.is_empty() {
+            writer.blank();
+            continue;
+        }
+
+        if keyword.starts_with("#+begin_src") || keyword.starts_with("#+begin_example") {
+            let language = trimmed.split_whitespace().nth(1).unwrap_or_default();
+            code = Some((language.to_string(), String::new()));
+            writer.flush();
+        } else if keyword.starts_with("#+begin_quote") {
+            writer.flush();
+            writer
+                .prefixes
+                .push(Span::styled("│ ", writer.theme.faint));
+            writer
+                .styles
+                .push(Style::default().add_modifier(Modifier::ITALIC));
+        } else if keyword.starts_with("#+end_quote") {
+            writer.end_quote();
+        } else if let Some(title) = keyword
+            .starts_with("#+title:")
+            .then(|| trimmed["#+title:".len()..].trim())
+        {
+            writer.flush();
+            writer.styled(title, writer.theme.heading_style(1));
+            writer.blank();
+        } else if trimmed.starts_with("#+") || trimmed.starts_with("# ") {
+            // other keywords and comments
+            writer.flush();
+            writer.styled(trimmed, writer.theme.faint);
+            writer.flush();
+        } else if let Some(level) = org_heading_level(line) {
+            writer.blank();
+            writer.headings.push(Heading {
+                level,
+                title: line[level..].trim().to_string(),
+                line: writer.lines.len(),
+            });
+            let style = writer.theme.heading_style(level);
+            writer.styled(&format!("{} ", "*".repeat(level)), style);
+            writer.styles.push(style);
+            org_inline(line[level..].trim(), writer);
+            writer.styles.pop();
+            writer.blank();
+        } else if let Some((indent, bullet, rest)) = list_item {
+            writer.flush();
+            if in_list {
+                writer.prefixes.pop();
+            }
+            in_list = true;
+            // a checkbox takes the place of the bullet
+            let (bullet, rest) = match rest.get(..4) {
+                Some("[ ] ") => (writer.checkbox(false), &rest[4..]),
+                Some("[X] " | "[x] ") => (writer.checkbox(true), &rest[4..]),
+                Some("[-] ") => (
+                    Span::styled("[-] ", writer.theme.partial),
+                    &rest[4..],
+                ),
+                _ => (Span::raw(format!("{} ", bullet)), rest),
+            };
+            let bullet = Span::styled(format!("{}{}", indent, bullet.content), bullet.style);
+            writer
+                .prefixes
+                .push(Span::raw(" ".repeat(bullet.content.chars().count())));
+            writer.bullet = Some(bullet);
+            org_inline(rest, writer);
+        } else if trimmed == ":" || trimmed.starts_with(": ") {
+            writer.flush();
+            writer.styled(trimmed.get(2..).unwrap_or_default(), writer.theme.code);
+            writer.flush();
+        } else if trimmed.len() >= 5 && trimmed.chars().all(|c| c == '-') {
+            writer.rule();
+        } else {
+            // the lines of a paragraph are joined together
+            if !writer.line.is_empty() {
+                writer.text(" ");
+            }
+            org_inline(trimmed, writer);
+        }
+    }
+
+    if let Some((language, code_text)) = code {
+        writer.code_block(&language, &code_text);
+    }
+}
+
+/// The level of an org heading, which is the number of stars it starts with.
+fn org_heading_level(line: &str) -> Option<usize> {
+    let stars = line.chars().take_while(|c| *c == '*').count();
+    (stars > 0 && line[stars..].starts_with(' ')).then_some(stars)
+}
+
+/// Splits an org list item into its indentation, bullet and text.
+fn org_list_item(line: &str) -> Option<(&str, &str, &str)> {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let rest = line.trim_start();
+    let (bullet, text) = rest.split_once(' ')?;
+    let is_bullet = matches!(bullet, "-" | "+")
+        || (bullet.len() > 1
+            && bullet.ends_with(['.', ')'])
+            && bullet[..bullet.len() - 1].chars().all(|c| c.is_ascii_digit()));
+    is_bullet.then_some((indent, bullet, text))
+}
+
+/// Writes text with org's inline markup, e.g. `*bold*`, `/italic/`, `~code~`
+/// and `[[link][description]]`.
+fn org_inline(text: &str, writer: &mut Writer) {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut plain = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        // links
+        if chars[i] == '[' && chars.get(i + 1) == Some(&'[') {
+            let rest = chars[i + 2..].iter().collect::<String>();
+            if let Some(end) = rest.find("]]") {
+                writer.text(&std::mem::take(&mut plain));
+                let link = &rest[..end];
+                let (target, description) = link.split_once("][").unwrap_or((link, link));
+                writer.styled(description, writer.theme.link);
+                if target != description {
+                    writer.styled(&format!(" ({})", target), writer.theme.faint);
+                }
+                i += 2 + rest[..end].chars().count() + 2;
+                continue;
+            }
+        }
+
+        let style = match chars[i] {
+            '*' => Some(Style::default().add_modifier(Modifier::BOLD)),
+            '/' => Some(Style::default().add_modifier(Modifier::ITALIC)),
+            '_' => Some(Style::default().add_modifier(Modifier::UNDERLINED)),
+            '+' => Some(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            '=' | '~' => Some(writer.theme.code),
+            _ => None,
+        };
+        let opens = i == 0 || chars[i - 1].is_whitespace() || "({'\"".contains(chars[i - 1]);
+        if let (Some(style), true) = (style, opens) {
+            let marker = chars[i];
+            let close = (i + 2..chars.len()).find(|j| {
+                chars[*j] == marker
+                    && !chars[*j - 1].is_whitespace()
+                    && chars
+                        .get(*j + 1)
+                        .is_none_or(|c| c.is_whitespace() || ".,;:!?')}\"".contains(*c))
+            });
+            if let Some(close) = close.filter(|_| !chars[i + 1].is_whitespace()) {
+                writer.text(&std::mem::take(&mut plain));
+                writer.styled(&chars[i + 1..close].iter().collect::<String>(), style);
+                i = close + 1;
+                continue;
+            }
+        }
+
+        plain.push(chars[i]);
+        i += 1;
+    }
+    writer.text(&plain);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_text(line: &Spans<'_>) -> String {
+        line.0.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    fn lines(note: &str, format: Format, width: usize) -> Vec<String> {
+        render(note, format, width, &Theme::default())
+            .lines
+            .iter()
+            .map(line_text)
+            .collect()
+    }
+
+    fn inline(text: &str) -> (Vec<(String, Style)>, Theme) {
+        let theme = Theme::default();
+        let mut writer = Writer::new(80, theme.clone());
+        org_inline(text, &mut writer);
+        let spans = writer
+            .line
+            .into_iter()
+            .map(|span| (span.content.into_owned(), span.style))
+            .collect();
+        (spans, theme)
+    }
+
+    #[test]
+    fn test_org_heading_level() {
+        assert_eq!(org_heading_level("* One"), Some(1));
+        assert_eq!(org_heading_level("*** Three"), Some(3));
+        assert_eq!(org_heading_level("*bold*"), None);
+        assert_eq!(org_heading_level(" * indented"), None);
+    }
+
+    #[test]
+    fn test_org_list_item() {
+        assert_eq!(org_list_item("- item"), Some(("", "-", "item")));
+        assert_eq!(org_list_item("  + nested item"), Some(("  ", "+", "nested item")));
+        assert_eq!(org_list_item("10. tenth"), Some(("", "10.", "tenth")));
+        assert_eq!(org_list_item("2) second"), Some(("", "2)", "second")));
+        assert_eq!(org_list_item("-item"), None);
+        assert_eq!(org_list_item(". item"), None);
+        assert_eq!(org_list_item("a. item"), None);
+    }
+
+    #[test]
+    fn test_org_inline() {
+        let (spans, theme) = inline("a *bold* and ~code~ [[https://x.org][site]]");
+        assert_eq!(spans, vec![
+            ("a ".to_string(), Style::default()),
+            ("bold".to_string(), Style::default().add_modifier(Modifier::BOLD)),
+            (" and ".to_string(), Style::default()),
+            ("code".to_string(), theme.code),
+            (" ".to_string(), Style::default()),
+            ("site".to_string(), theme.link),
+            (" (https://x.org)".to_string(), theme.faint),
+        ]);
+    }
+
+    #[test]
+    fn test_org_inline_leaves_plain_text() {
+        let plain = "a*b* 2/3/4 snake_case_name * not bold*";
+        assert_eq!(inline(plain).0, vec![(plain.to_string(), Style::default())]);
+        // a link without a description is only shown once
+        let (spans, theme) = inline("[[https://x.org]]");
+        assert_eq!(spans, vec![("https://x.org".to_string(), theme.link)]);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(lines("one two three four", Format::Plain, 9), vec![
+            "one two ", "three ", "four",
+        ]);
+        // words longer than the width are broken
+        assert_eq!(lines("abcdefghij", Format::Plain, 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_wrap_keeps_list_indentation() {
+        assert_eq!(lines("- first item here\n- [x] done", Format::Org, 10), vec![
+            "- first ", "  item ", "  here", "[x] done",
+        ]);
+    }
+
+    #[test]
+    fn test_org_headings() {
+        let rendered = render("* One\ntext\n** Two", Format::Org, 80, &Theme::default());
+        assert_eq!(rendered.lines.iter().map(line_text).collect::<Vec<_>>(), vec![
+            "* One", "", "text", "", "** Two",
+        ]);
+        let headings = rendered
+            .headings
+            .iter()
+            .map(|heading| (heading.level, heading.title.as_str(), heading.line))
+            .collect::<Vec<_>>();
+        assert_eq!(headings, vec![(1, "One", 0), (2, "Two", 4)]);
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_eq!(lines("# Title\n\nsome *text*\n\n> quoted", Format::Markdown, 80), vec![
+            "# Title", "", "some text", "", "│ quoted",
+        ]);
+    }
+}
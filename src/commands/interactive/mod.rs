@@ -1,29 +1,74 @@
 #![allow(unused)]
+mod annotations;
+mod bulk;
+mod columns;
+mod command;
+mod conflict;
+mod editor;
 mod events;
+mod input;
+mod keymap;
+mod markup;
+mod modify;
+mod preview;
+mod search;
+mod theme;
+mod tree;
 
 use anyhow::{anyhow, Context, Result};
 use std::{
-    io::{self, Stdout, Write},
-    process::Command,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 
 use termion::{
-    event::Key,
+    event::{Key, MouseButton, MouseEvent},
     input::MouseTerminal,
-    raw::{IntoRawMode, RawTerminal},
+    raw::IntoRawMode,
     screen::AlternateScreen,
 };
 use tui::{
     backend::TermionBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    style::Modifier,
+    text::{Span, Spans, Text},
+    widgets::{
+        Block, BorderType, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table,
+        TableState, Wrap,
+    },
     Terminal,
 };
 
-use crate::{opt::Opt, taskwarrior::Task};
-use events::{Event, Events};
+use super::edit;
+use crate::{
+    config::Config,
+    crypt,
+    journal::{Change, Journal, Operation},
+    links,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskwarrior::{self, ParsableDateTime, Task},
+};
+use annotations::{AnnotatePrompt, Annotations};
+use bulk::{Action, BulkPrompt, Confirm, Field};
+use columns::{Column, Columns};
+use command::CommandPrompt;
+use conflict::{Conflict, Resolve};
+use editor::NoteEditor;
+use events::{Changed, Event, Events, Watch};
+use input::{History, LineInput};
+use keymap::{Command, Help, Keymap, Scope};
+use modify::Modify;
+use preview::{Preview, PreviewState};
+use search::{Search, SearchPrompt};
+use theme::Theme;
+use tree::Entry;
 
 #[derive(Debug, Error)]
 pub(crate) enum Error {
@@ -52,43 +97,121 @@ pub(crate) enum Error {
 }
 
 // type Term = Terminal<TermionBackend<RawTerminal<Stdout>>>;
-type Term = Terminal<TermionBackend<AlternateScreen<MouseTerminal<RawTerminal<Stdout>>>>>;
+/// The raw terminal, which reports mouse events unless the mouse is disabled
+type Output = AlternateScreen<Box<dyn Write>>;
+type Term = Terminal<TermionBackend<Output>>;
 
 pub(crate) fn execute(opt: &Opt) -> Result<()> {
-    let stdout = io::stdout().into_raw_mode()?;
-    let stdout = MouseTerminal::from(stdout);
-    let stdout = AlternateScreen::from(stdout);
-    let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    // prompt for the passphrase before the terminal is taken over
+    if opt.encrypt {
+        crypt::unlock(opt)?;
+    }
 
-    let mut taskwarrior_args = opt.args.clone();
-    taskwarrior_args.push("(status:pending or status:waiting)".to_string());
+    let config = Config::load(opt)?;
+    let columns = columns::from_config(&config.interactive)?;
+    let keymap = keymap::from_config(&config.interactive.keys)?;
+    let theme = theme::from_config(&config.interactive)?;
 
-    terminal.hide_cursor()?;
-    terminal.clear()?;
+    let mouse = config.interactive.mouse;
+    let mut terminal = make_terminal(mouse)?;
 
-    let events = Events::new();
-    let mut common_state = CommonState::load_from_taskwarrior(opt)?;
+    let mut common_state = CommonState::load_from_taskwarrior(opt, opt.args.clone())?;
+    // nothing creates the root directory until a note is written, but it has
+    // to exist to be watched
+    fs::create_dir_all(&opt.root_dir).context("error creating the notes directory")?;
+    let events = Events::new(&Watch {
+        tasks: taskwarrior::data_location()
+            .and_then(|location| location.canonicalize())
+            .ok(),
+        notes: Path::new(&opt.root_dir)
+            .canonicalize()
+            .context("error finding the notes directory")?,
+    })?;
+    common_state.columns = columns;
+    common_state.keymap = keymap;
+    common_state.theme = theme;
+    common_state.detail = config.interactive.detail;
+    common_state.tree = config.interactive.tree;
     let mut mode: Box<dyn Mode> = Box::new(Normal);
+    // what changed outside of taskn and hasn't been loaded yet
+    let mut outdated = None;
     loop {
-        mode.render(&mut common_state, &mut terminal)?;
-        match events.next().map_err(Error::NextIterator)? {
+        terminal
+            .draw(|frame| {
+                let (main, status) = status_layout(frame.size());
+                mode.render(&mut common_state, frame, main);
+                render_status(frame, &common_state, mode.name(), status);
+            })
+            .context("error drawing terminal")?;
+        let result = match events.next().map_err(Error::NextIterator)? {
             Event::Key(key) => match key {
-                Key::Char('q') | Key::Esc | Key::Ctrl('c') => break,
+                Key::Ctrl('c') => break,
                 key => {
-                    let result = mode.update(opt, &mut common_state, key)?;
-                    if let Some(new_mode) = result.new_mode {
-                        mode = new_mode;
-                    }
-                    if result.should_flush {
-                        common_state = common_state.flush_to_taskwarrior(opt)?;
-                    } else if result.should_load {
-                        common_state = CommonState::load_from_taskwarrior(opt)?;
-                    }
+                    common_state.message = None;
+                    mode.update(opt, &mut common_state, key)
                 },
             },
-            Event::Resize => continue,
+            Event::Mouse(event) if mouse => mode.mouse(opt, &mut common_state, event),
+            Event::Changed(changed) => {
+                outdated = outdated.max(Some(changed));
+                Ok(ActionResult::default())
+            },
+            Event::Mouse(_) | Event::Resize => continue,
+        };
+        // a failed action is reported in the status bar, leaving the mode as it was
+        let mut result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                common_state.show_error(&e);
+                ActionResult::default()
+            },
+        };
+        if result.should_quit {
+            break;
+        }
+        if let Some(new_mode) = result.new_mode {
+            mode = new_mode;
+        }
+        let open_notes = result.open_notes;
+        if !open_notes.is_empty() {
+            let (resumed, edited) = suspend(terminal, mouse, || edit::edit(opt, &open_notes))?;
+            terminal = resumed;
+            if let Err(e) = edited {
+                common_state.show_error(&e);
+            }
+            result.should_load = true;
+        }
+        if let Some(task) = result.edit_task.take() {
+            let (resumed, edited) = suspend(terminal, mouse, || Ok(task_edit(&task.uuid)?))?;
+            terminal = resumed;
+            if let Err(e) = edited {
+                common_state.show_error(&e);
+            }
+            result.should_load = true;
         }
+        if result.should_flush {
+            match common_state.flush_to_taskwarrior(opt) {
+                Ok(conflicts) if !conflicts.is_empty() => mode = Box::new(Resolve::new(conflicts)),
+                Ok(_) => {},
+                Err(e) => common_state.show_error(&e),
+            }
+        } else if result.should_load {
+            if let Err(e) = common_state.reload(opt) {
+                common_state.show_error(&e);
+            }
+        }
+        // changes made elsewhere wait until the mode isn't holding on to tasks
+        if let Some(changed) = outdated.filter(|_| mode.allows_reload()) {
+            let reloaded = match changed {
+                Changed::Tasks => common_state.reload(opt),
+                Changed::Notes => common_state.reload_notes(opt),
+            };
+            if let Err(e) = reloaded {
+                common_state.show_error(&e);
+            }
+            outdated = None;
+        }
+        common_state.keep_cursor_visible();
     }
 
     terminal.show_cursor()?;
@@ -98,90 +221,807 @@ pub(crate) fn execute(opt: &Opt) -> Result<()> {
     Ok(())
 }
 
+fn make_terminal(mouse: bool) -> Result<Term> {
+    let stdout = io::stdout().into_raw_mode()?;
+    let stdout: Box<dyn Write> = if mouse {
+        Box::new(MouseTerminal::from(stdout))
+    } else {
+        Box::new(stdout)
+    };
+    let stdout = AlternateScreen::from(stdout);
+    let backend = TermionBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    terminal.hide_cursor()?;
+    terminal.clear()?;
+    Ok(terminal)
+}
+
+/// Hands the terminal back to the shell while running `f`, e.g. so that an
+/// editor can be opened, and then takes it over again. What `f` returned is
+/// given back along with the terminal.
+fn suspend<F: FnOnce() -> Result<()>>(
+    terminal: Term,
+    mouse: bool,
+    f: F,
+) -> Result<(Term, Result<()>)> {
+    // dropping the terminal leaves the alternate screen and raw mode
+    drop(terminal);
+    let result = f();
+    Ok((make_terminal(mouse)?, result))
+}
+
+/// Runs `task edit` on a task, which opens the task in an editor.
+fn task_edit(uuid: &str) -> Result<(), Error> {
+    let child = process::Command::new("task")
+        .arg(uuid)
+        .arg("edit")
+        .spawn()
+        .map_err(|_| Error::TaskUUID {
+            command: String::from("edit"),
+            uuid:    uuid.to_string(),
+        })?;
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(Error::TaskCmd {
+            command: String::from("edit"),
+            uuid:    uuid.to_string(),
+            stdout:  String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr:  String::from_utf8_lossy(&output.stderr).to_string(),
+        }),
+        Err(err) => Err(Error::TaskCmdNoStdout {
+            command: String::from("edit"),
+            uuid: uuid.to_string(),
+            err,
+        }),
+    }
+}
+
 struct CommonState {
-    list_state:     ListState,
-    tasks:          Vec<Task>,
+    list_state:      ListState,
+    tasks:           Vec<Task>,
     // TODO: right now we represent the contents of a task on this [CommonState]
     // but it seems like it ought to be on the task instead, since it's specifically
     // that task's contents
     // think about moving this onto the [Task].
-    tasks_contents: Vec<(String, String)>,
+    tasks_contents:  Vec<(String, String)>,
+    /// Maps the UUID of a task to the UUIDs of the tasks whose notes link to it
+    backlinks:       HashMap<String, Vec<String>>,
+    /// The taskwarrior filter selecting which tasks are shown
+    filter:          Vec<String>,
+    /// Filters previously entered in [FilterPrompt]
+    filter_history:  History,
+    /// Commands previously run from [CommandPrompt]
+    command_history: History,
+    /// The active search, if any
+    search:          Option<Search>,
+    /// The columns of the task list
+    columns:         Columns,
+    /// The keys bound to commands, and the keys typed so far
+    keymap:          Keymap,
+    theme:           Theme,
+    /// Whether the attributes of the selected task are shown
+    detail:          bool,
+    /// How the notes are marked up, for rendering them in the preview
+    format:          markup::Format,
+    /// How far the preview of each task's note is scrolled, by UUID
+    scroll:          HashMap<String, usize>,
+    /// The size and headings of the preview as it was last drawn
+    preview:         PreviewState,
+    /// Whether tasks are grouped into a tree of their projects
+    tree:            bool,
+    /// The projects whose tasks are hidden in the tree
+    collapsed:       HashSet<String>,
+    /// The project whose header is under the cursor, instead of a task
+    selected_group:  Option<String>,
+    /// Where the rows of the task list were last drawn, to find the row under
+    /// the mouse
+    list_area:       Rect,
+    /// The row drawn at the top of the task list
+    list_offset:     usize,
+    /// When, and on which row, the mouse was last clicked
+    last_click:      Option<(Instant, usize)>,
+    /// The UUIDs of the tasks marked for a bulk action
+    marked:          HashSet<String>,
+    /// The UUID of the task where a range selection started with `V`
+    anchor:          Option<String>,
+    /// The tasks as they were loaded from taskwarrior, by UUID, to tell what
+    /// was changed when flushing
+    saved:           HashMap<String, Task>,
+    /// The operations which can be undone, the latest last
+    undo:            Vec<Operation>,
+    /// The operations which were undone and can be made again
+    redo:            Vec<Operation>,
+    /// The UUIDs of the tasks in the order they were loaded, to tell which
+    /// were moved
+    loaded_order:    Vec<String>,
+    /// How many tasks are pending and waiting in all of taskwarrior
+    counts:          (usize, usize),
+    /// Shown in the status bar until the next key is pressed
+    message:         Option<Message>,
+}
+
+/// A note about the last action, or the reason it failed
+struct Message {
+    text:  String,
+    error: bool,
 }
 
 impl CommonState {
-    fn load_from_taskwarrior(opt: &Opt) -> Result<Self> {
-        let mut tasks = {
-            if opt.only_taskn {
-                Task::get(["status:pending", "+taskn"].iter())
-            } else {
-                Task::get(["status:pending"].iter())
-            }
-        }
-        .context("error with task output from arguments: status:pending")?;
+    fn load_from_taskwarrior(opt: &Opt, filter: Vec<String>) -> Result<Self> {
+        let args = filter_args(opt, &filter);
+        let mut tasks = Task::get(args.iter()).with_context(|| {
+            format!("error with task output from arguments: {}", args.join(" "))
+        })?;
 
-        tasks.sort_by(|a, b| a.estimate.partial_cmp(&b.estimate).unwrap());
+        tasks.sort_by(|a, b| {
+            a.manual_order()
+                .partial_cmp(&b.manual_order())
+                .unwrap_or(Ordering::Equal)
+        });
 
         let mut list_state = ListState::default();
         if !tasks.is_empty() {
             list_state.select(Some(0));
         }
 
-        let mut tasks_contents = Vec::with_capacity(tasks.len());
-        for task in &tasks {
-            tasks_contents.push((task.uuid.clone(), task.load_contents(opt)?));
-        }
+        let (tasks_contents, backlinks) = load_notes(opt, &tasks)?;
+        let saved = tasks
+            .iter()
+            .map(|task| (task.uuid.clone(), task.clone()))
+            .collect();
+        let loaded_order = tasks.iter().map(|task| task.uuid.clone()).collect();
+        let counts = (
+            taskwarrior::count(&status_args(opt, "pending"))?,
+            taskwarrior::count(&status_args(opt, "waiting"))?,
+        );
 
         Ok(CommonState {
             list_state,
             tasks,
             tasks_contents,
+            backlinks,
+            filter,
+            filter_history: History::default(),
+            command_history: History::default(),
+            search: None,
+            columns: vec![],
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            detail: false,
+            format: markup::Format::from_extension(&opt.file_format),
+            scroll: HashMap::new(),
+            preview: PreviewState::default(),
+            tree: false,
+            collapsed: HashSet::new(),
+            selected_group: None,
+            list_area: Rect::default(),
+            list_offset: 0,
+            last_click: None,
+            marked: HashSet::new(),
+            anchor: None,
+            saved,
+            undo: vec![],
+            redo: vec![],
+            loaded_order,
+            counts,
+            message: None,
         })
     }
 
-    fn flush_to_taskwarrior(self, opt: &Opt) -> Result<Self> {
-        // need to calculate new_selected before into_iter()
-        // because otherwise it would partially move out of self
-        // and cause a compiler error
-        let mut new_selected = self.selected();
-        for (order, mut task) in self.tasks.into_iter().enumerate() {
-            task.estimate = Some(order.to_string());
-            task.save()?;
+    /// Loads tasks from taskwarrior again, keeping the filter, its history and
+    /// (as far as possible) the selected task.
+    fn reload(&mut self, opt: &Opt) -> Result<()> {
+        let selected = self.selected();
+        let selected_uuid = self.tasks.get(selected).map(|task| task.uuid.clone());
+        let fresh = Self::load_from_taskwarrior(opt, self.filter.clone())
+            .context("error loading new data from task")?;
+        self.tasks = fresh.tasks;
+        self.tasks_contents = fresh.tasks_contents;
+        self.backlinks = fresh.backlinks;
+        self.saved = fresh.saved;
+        self.loaded_order = fresh.loaded_order;
+        self.counts = fresh.counts;
+        self.list_state = fresh.list_state;
+        // tasks which are no longer shown can't be acted on
+        let loaded = self
+            .tasks
+            .iter()
+            .map(|task| task.uuid.clone())
+            .collect::<HashSet<_>>();
+        self.marked.retain(|uuid| loaded.contains(uuid));
+        self.anchor = self.anchor.take().filter(|anchor| loaded.contains(anchor));
+        // the selected task may have moved, e.g. when tasks were added above it
+        let moved = self
+            .tasks
+            .iter()
+            .position(|task| Some(&task.uuid) == selected_uuid.as_ref());
+        if let Some(index) = moved {
+            self.list_state.select(Some(index));
+        } else if !self.tasks.is_empty() {
+            self.list_state.select(Some(selected.min(self.tasks.len() - 1)));
+        }
+        Ok(())
+    }
+
+    /// Reads the notes again, without asking taskwarrior for the tasks.
+    fn reload_notes(&mut self, opt: &Opt) -> Result<()> {
+        let (tasks_contents, backlinks) = load_notes(opt, &self.tasks)?;
+        self.tasks_contents = tasks_contents;
+        self.backlinks = backlinks;
+        Ok(())
+    }
+
+    /// Writes the tasks which were changed back to taskwarrior, in their
+    /// manual order. Tasks which were also changed elsewhere since they were
+    /// loaded are left alone, and returned as conflicts to be resolved.
+    fn flush_to_taskwarrior(&mut self, opt: &Opt) -> Result<Vec<Conflict>> {
+        let mut changed = vec![];
+        for (order, task) in self.tasks.iter().enumerate() {
+            let mut task = task.clone();
+            #[allow(clippy::cast_precision_loss)]
+            if task.manual_order() != Some(order as f64) {
+                task.estimate = Some(order.to_string());
+            }
+            if let Some(base) = self.saved.get(&task.uuid) {
+                if Change::new(base, &task).is_some() {
+                    changed.push((base.clone(), task));
+                }
+            }
+        }
+
+        let conflicts = self.save_unless_changed(opt, changed)?;
+        self.reload(opt)?;
+        Ok(conflicts)
+    }
+
+    /// Saves tasks changed in taskn, each paired with the task as it was
+    /// loaded, and records the changes. Tasks which were also changed
+    /// elsewhere since they were loaded are left alone, and returned as
+    /// conflicts to be resolved.
+    fn save_unless_changed(
+        &mut self,
+        opt: &Opt,
+        changed: Vec<(Task, Task)>,
+    ) -> Result<Vec<Conflict>> {
+        let mut current = if changed.is_empty() {
+            vec![]
+        } else {
+            Task::get(changed.iter().map(|(base, _)| &base.uuid))
+                .context("error checking tasks for changes made elsewhere")?
+        };
+        let mut changes = vec![];
+        let mut conflicts = vec![];
+        for (base, mine) in changed {
+            let theirs = current
+                .iter()
+                .position(|theirs| theirs.uuid == base.uuid)
+                .map(|index| current.swap_remove(index));
+            match theirs {
+                Some(theirs) if theirs.modified != base.modified =>
+                    conflicts.push(Conflict { base, mine, theirs }),
+                _ => {
+                    save_task(opt, &mine, &base)?;
+                    changes.extend(Change::new(&base, &mine));
+                },
+            }
         }
-        let mut new_self =
-            Self::load_from_taskwarrior(opt).context("error loading new data from task")?;
+        self.record(changes)?;
+        Ok(conflicts)
+    }
 
-        if new_selected >= new_self.tasks.len() {
-            new_selected = new_self.tasks.len() - 1;
+    /// Records changes made to tasks, so that they can be undone.
+    fn record(&mut self, changes: Vec<Change>) -> Result<()> {
+        if let Some(operation) = Operation::new(changes) {
+            Journal::open().push(&operation)?;
+            self.show_message(format!("Changed {}", operation.summary));
+            self.undo.push(operation);
+            self.redo.clear();
         }
-        new_self.list_state.select(Some(new_selected));
-        Ok(new_self)
+        Ok(())
+    }
+
+    /// Puts back the tasks changed by the latest operation. Returns whether
+    /// there was anything to undo.
+    fn undo(&mut self) -> Result<bool> {
+        let operation = match self.undo.pop() {
+            Some(operation) => operation,
+            None => {
+                self.show_message("Nothing to undo");
+                return Ok(false);
+            },
+        };
+        operation.undo()?;
+        Journal::open().forget(&operation)?;
+        self.show_message(format!("Undid {}", operation.summary));
+        self.redo.push(operation);
+        Ok(true)
+    }
+
+    /// Makes the latest undone operation again. Returns whether there was
+    /// anything to redo.
+    fn redo(&mut self) -> Result<bool> {
+        let operation = match self.redo.pop() {
+            Some(operation) => operation,
+            None => {
+                self.show_message("Nothing to redo");
+                return Ok(false);
+            },
+        };
+        operation.redo()?;
+        Journal::open().push(&operation)?;
+        self.show_message(format!("Redid {}", operation.summary));
+        self.undo.push(operation);
+        Ok(true)
     }
 
     fn selected(&self) -> usize {
         self.list_state.selected().unwrap_or(0)
     }
 
+    /// Shows a message in the status bar.
+    fn show_message<S: Into<String>>(&mut self, text: S) {
+        self.message = Some(Message {
+            text:  text.into(),
+            error: false,
+        });
+    }
+
+    /// Shows an error in the status bar, along with what caused it, instead of
+    /// leaving the interactive view.
+    fn show_error(&mut self, error: &anyhow::Error) {
+        self.message = Some(Message {
+            text:  format!("{:#}", error),
+            error: true,
+        });
+    }
+
+    /// The number of tasks which were changed or moved but aren't written to
+    /// taskwarrior yet.
+    fn unsaved(&self) -> usize {
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter(|(index, task)| {
+                self.loaded_order.get(*index) != Some(&task.uuid)
+                    || self
+                        .saved
+                        .get(&task.uuid)
+                        .is_some_and(|base| Change::new(base, task).is_some())
+            })
+            .count()
+    }
+
+    /// The task under the cursor, which is none when the cursor is on a
+    /// project in the tree.
+    fn selected_task(&self) -> Option<&Task> {
+        if self.selected_group.is_some() {
+            return None;
+        }
+        self.tasks.get(self.selected())
+    }
+
+    /// Selects a task, expanding the projects it is hidden in.
+    fn select_task(&mut self, index: usize) {
+        self.list_state.select(Some(index));
+        self.selected_group = None;
+        if let Some(project) = self.tasks.get(index).and_then(|task| task.project.as_ref()) {
+            for ancestor in tree::ancestors(project) {
+                self.collapsed.remove(&ancestor);
+            }
+        }
+    }
+
+    /// The rows of the task list, leaving out the tasks hidden by a search.
+    fn entries(&self) -> Vec<Entry> {
+        let matches = self.search_matches();
+        let narrow = self
+            .search
+            .as_ref()
+            .is_some_and(|search| search.narrow && !search.query.is_empty());
+        let shown = (0..self.tasks.len())
+            .filter(|index| !narrow || matches.iter().any(|m| m.index == *index))
+            .collect::<Vec<_>>();
+        if self.tree {
+            tree::entries(&self.tasks, &shown, &self.collapsed)
+        } else {
+            shown
+                .into_iter()
+                .map(|index| Entry::Task { index, depth: 0 })
+                .collect()
+        }
+    }
+
+    /// The row of the cursor within the entries.
+    fn cursor(&self, entries: &[Entry]) -> Option<usize> {
+        entries.iter().position(|entry| match entry {
+            Entry::Group(group) => self.selected_group.as_ref() == Some(&group.project),
+            Entry::Task { index, .. } =>
+                self.selected_group.is_none() && *index == self.selected(),
+        })
+    }
+
+    fn select_entry(&mut self, entry: &Entry) {
+        match entry {
+            Entry::Group(group) => self.selected_group = Some(group.project.clone()),
+            Entry::Task { index, .. } => self.select_task(*index),
+        }
+    }
+
+    /// Moves the cursor to the next (or previous) row.
+    fn move_cursor(&mut self, forward: bool) {
+        let entries = self.entries();
+        let next = match self.cursor(&entries) {
+            Some(cursor) if forward => cursor + 1,
+            Some(cursor) => match cursor.checked_sub(1) {
+                Some(previous) => previous,
+                None => return,
+            },
+            None => 0,
+        };
+        if let Some(entry) = entries.get(next) {
+            self.select_entry(entry);
+        }
+    }
+
+    /// Moves the cursor to the first (or last) row.
+    fn move_cursor_to_end(&mut self, last: bool) {
+        let entries = self.entries();
+        let entry = if last { entries.last() } else { entries.first() };
+        if let Some(entry) = entry {
+            self.select_entry(entry);
+        }
+    }
+
+    /// Moves the cursor onto the header of a collapsed project when the
+    /// selected task is hidden inside of it, and off of projects which are no
+    /// longer shown.
+    fn keep_cursor_visible(&mut self) {
+        if !self.tree {
+            self.selected_group = None;
+            return;
+        }
+        if let Some(group) = &self.selected_group {
+            let exists = self
+                .tasks
+                .iter()
+                .filter_map(|task| task.project.as_ref())
+                .any(|project| tree::ancestors(project).contains(group));
+            if !exists {
+                self.selected_group = None;
+            }
+            return;
+        }
+        let project = self.tasks.get(self.selected()).and_then(|task| task.project.as_ref());
+        if let Some(project) = project {
+            self.selected_group = tree::ancestors(project)
+                .into_iter()
+                .find(|ancestor| self.collapsed.contains(ancestor));
+        }
+    }
+
+    /// Hides the tasks of the project under the cursor, or of the selected
+    /// task's project. On a project which is already collapsed, moves to the
+    /// project containing it.
+    fn collapse(&mut self) {
+        let project = match &self.selected_group {
+            Some(group) if self.collapsed.contains(group) => {
+                if let Some((parent, _)) = group.rsplit_once('.') {
+                    self.selected_group = Some(parent.to_string());
+                }
+                return;
+            },
+            Some(group) => group.clone(),
+            None => match self.selected_task().and_then(|task| task.project.clone()) {
+                Some(project) => project,
+                None => return,
+            },
+        };
+        self.collapsed.insert(project.clone());
+        self.selected_group = Some(project);
+    }
+
+    /// Shows the tasks of the project under the cursor again.
+    fn expand(&mut self) {
+        if let Some(group) = &self.selected_group {
+            self.collapsed.remove(group);
+        }
+    }
+
+    fn toggle_collapsed(&mut self) {
+        match &self.selected_group {
+            Some(group) if self.collapsed.contains(group) => self.expand(),
+            Some(_) => self.collapse(),
+            None => {},
+        }
+    }
+
+    /// The filter as it would be typed on the command line.
+    fn filter_text(&self) -> String {
+        shell_words::join(&self.filter)
+    }
+
     fn selected_contents(&self) -> String {
+        self.selected_task()
+            .map(|task| self.contents_of(&task.uuid))
+            .unwrap_or_default()
+    }
+
+    /// The contents of the note of a loaded task.
+    fn contents_of(&self, uuid: &str) -> String {
+        self.tasks_contents
+            .iter()
+            .find(|(task_uuid, _)| task_uuid == uuid)
+            .map(|(_, contents)| contents.clone())
+            .unwrap_or_default()
+    }
+
+    fn search_matches(&self) -> Vec<search::Match> {
+        self.search
+            .as_ref()
+            .map(|search| search.matches(&self.tasks, |uuid| self.contents_of(uuid)))
+            .unwrap_or_default()
+    }
+
+    /// Selects the next (or previous) task matching the search, wrapping
+    /// around the ends of the list.
+    fn jump_to_match(&mut self, forward: bool) {
+        let matches = self.search_matches();
         let selected = self.selected();
-        let selected_uuid = &self.tasks[selected].uuid;
-        for (uuid, contents) in self.tasks_contents.clone() {
-            if *selected_uuid == uuid {
-                return contents;
+        let next = if forward {
+            matches
+                .iter()
+                .find(|m| m.index > selected)
+                .or_else(|| matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|m| m.index < selected)
+                .or_else(|| matches.last())
+        };
+        if let Some(next) = next {
+            self.select_task(next.index);
+        }
+    }
+
+    /// How far the preview of the selected task's note is scrolled.
+    fn preview_scroll(&self) -> usize {
+        self.selected_task()
+            .and_then(|task| self.scroll.get(&task.uuid))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Scrolls the preview to a line, keeping the end of the note at the
+    /// bottom of the pane.
+    fn set_preview_scroll(&mut self, line: usize) {
+        let last = self.preview.lines.saturating_sub(self.preview.height);
+        if let Some(uuid) = self.selected_task().map(|task| task.uuid.clone()) {
+            self.scroll.insert(uuid, line.min(last));
+        }
+    }
+
+    /// The row of the task list under the mouse, if any. Termion counts
+    /// positions from 1.
+    fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        let (x, y) = (x.saturating_sub(1), y.saturating_sub(1));
+        if !contains(self.list_area, x, y) {
+            return None;
+        }
+        let row = self.list_offset + usize::from(y - self.list_area.y);
+        Some(row).filter(|row| *row < self.entries().len())
+    }
+
+    /// The tasks which are marked, including the range being selected, in the
+    /// order they're listed.
+    fn marked_tasks(&self) -> Vec<usize> {
+        let mut range = HashSet::new();
+        if let Some(anchor) = &self.anchor {
+            let entries = self.entries();
+            let start = entries.iter().position(|entry| match entry {
+                Entry::Task { index, .. } => &self.tasks[*index].uuid == anchor,
+                Entry::Group(_) => false,
+            });
+            if let (Some(start), Some(cursor)) = (start, self.cursor(&entries)) {
+                range = entries[start.min(cursor)..=start.max(cursor)]
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        Entry::Task { index, .. } => Some(*index),
+                        Entry::Group(_) => None,
+                    })
+                    .collect();
             }
         }
-        panic!("selected invariant violated");
+        (0..self.tasks.len())
+            .filter(|index| range.contains(index) || self.marked.contains(&self.tasks[*index].uuid))
+            .collect()
+    }
+
+    /// The tasks a bulk action applies to: the marked tasks, or else the
+    /// selected one.
+    fn targets(&self) -> Vec<usize> {
+        let marked = self.marked_tasks();
+        if !marked.is_empty() {
+            return marked;
+        }
+        match self.selected_task() {
+            Some(_) => vec![self.selected()],
+            None => vec![],
+        }
+    }
+
+    /// Marks (or unmarks) the selected task.
+    fn toggle_mark(&mut self) {
+        if let Some(uuid) = self.selected_task().map(|task| task.uuid.clone()) {
+            if !self.marked.remove(&uuid) {
+                self.marked.insert(uuid);
+            }
+        }
+    }
+
+    /// Starts selecting a range from the selected task, or marks the range
+    /// selected so far.
+    fn toggle_range(&mut self) {
+        if self.anchor.is_some() {
+            let range = self
+                .marked_tasks()
+                .into_iter()
+                .map(|index| self.tasks[index].uuid.clone())
+                .collect::<Vec<_>>();
+            self.marked.extend(range);
+            self.anchor = None;
+        } else {
+            self.anchor = self.selected_task().map(|task| task.uuid.clone());
+        }
+    }
+
+    fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.anchor = None;
+    }
+
+    /// Whether the preview is under the mouse.
+    fn preview_at(&self, x: u16, y: u16) -> bool {
+        contains(self.preview.area, x.saturating_sub(1), y.saturating_sub(1))
+    }
+
+    /// Notes a click on a row, returning whether it was the second click of a
+    /// double click.
+    fn click(&mut self, row: usize) -> bool {
+        let now = Instant::now();
+        let double = self.last_click.is_some_and(|(time, last)| {
+            last == row && now.duration_since(time) < DOUBLE_CLICK
+        });
+        self.last_click = if double { None } else { Some((now, row)) };
+        double
+    }
+
+    /// Scrolls the preview down (or up) by a number of lines.
+    fn scroll_preview(&mut self, forward: bool, lines: usize) {
+        let scroll = self.preview_scroll();
+        if forward {
+            self.set_preview_scroll(scroll + lines);
+        } else {
+            self.set_preview_scroll(scroll.saturating_sub(lines));
+        }
+    }
+
+    /// The descriptions of the tasks whose notes link to the selected task.
+    /// Tasks which are not loaded are shown by their UUID.
+    fn selected_backlinks(&self) -> Vec<String> {
+        let selected_uuid = match self.selected_task() {
+            Some(task) => &task.uuid,
+            None => return vec![],
+        };
+        self.backlinks
+            .get(selected_uuid)
+            .map(|sources| {
+                sources
+                    .iter()
+                    .map(|source| {
+                        self.tasks
+                            .iter()
+                            .find(|task| notes::key_matches(source, &task.uuid))
+                            .map_or_else(|| source.clone(), |task| task.description.clone())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 }
 
+/// Writes a task to taskwarrior, along with its place in the manual order when
+/// that differs from before. A note with a readable name is renamed to follow
+/// the task's new description or project.
+fn save_task(opt: &Opt, task: &Task, before: &Task) -> Result<()> {
+    if task.estimate == before.estimate {
+        task.save()?;
+    } else {
+        #[allow(clippy::cast_possible_truncation)]
+        let order = task.manual_order().map(|order| order as i32);
+        let order = order.map_or_else(String::new, |order| order.to_string());
+        task.save_with(&[format!("estimate:{}", order)])?;
+    }
+    if task.description != before.description || task.project != before.project {
+        Resolver::new(opt)?.rename(task)?;
+    }
+    Ok(())
+}
+
+/// Reads the note of each task, along with the links between all of the notes.
+#[allow(clippy::type_complexity)]
+fn load_notes(
+    opt: &Opt,
+    tasks: &[Task],
+) -> Result<(Vec<(String, String)>, HashMap<String, Vec<String>>)> {
+    let resolver = Resolver::new(opt).context("error reading notes directory")?;
+    let mut tasks_contents = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        tasks_contents.push((task.uuid.clone(), task.load_contents(&resolver)?));
+    }
+
+    let backlinks = links::backlinks(&resolver).context("error reading note links")?;
+    Ok((tasks_contents, backlinks))
+}
+
+/// Builds the arguments given to `task export` for a filter. Only pending tasks
+/// are shown unless the filter asks for a status itself.
+fn filter_args(opt: &Opt, filter: &[String]) -> Vec<String> {
+    let mut args = vec![];
+    if !filter.iter().any(|arg| arg.starts_with("status:")) {
+        args.push("status:pending".to_string());
+    }
+    if opt.only_taskn {
+        args.push("+taskn".to_string());
+    }
+    if !filter.is_empty() {
+        // keep an `or` inside of the filter from escaping the status
+        args.push("(".to_string());
+        args.extend(filter.iter().cloned());
+        args.push(")".to_string());
+    }
+    args
+}
+
+/// The arguments to `task count` for every task with a status.
+fn status_args(opt: &Opt, status: &str) -> Vec<String> {
+    let mut args = vec![format!("status:{}", status)];
+    if opt.only_taskn {
+        args.push("+taskn".to_string());
+    }
+    args
+}
+
+/// The most time between the clicks of a double click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// The number of lines scrolled by a turn of the mouse wheel
+const WHEEL_LINES: usize = 3;
+
+/// Whether a position, counted from 0, is inside an area.
+fn contains(area: Rect, x: u16, y: u16) -> bool {
+    x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height
+}
+
 #[derive(Default)]
 struct ActionResult {
     new_mode:     Option<Box<dyn Mode>>,
     should_load:  bool,
     should_flush: bool,
+    /// Tasks whose notes are opened in the editor
+    open_notes:   Vec<Task>,
+    /// A task which is opened with `task edit`
+    edit_task:    Option<Task>,
+    should_quit:  bool,
 }
 
 trait Mode {
-    fn render(&self, common_state: &mut CommonState, terminal: &mut Term) -> Result<()>;
+    /// The name of the mode, as shown in the status bar
+    fn name(&self) -> &'static str;
+
+    /// Draws the mode into the area above the status bar.
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect);
 
     fn update(
         &mut self,
@@ -189,6 +1029,22 @@ trait Mode {
         common_state: &mut CommonState,
         key: Key,
     ) -> Result<ActionResult>;
+
+    /// Handles a click, drag or scroll of the mouse. Ignored by default.
+    fn mouse(
+        &mut self,
+        _opt: &Opt,
+        _common_state: &mut CommonState,
+        _event: MouseEvent,
+    ) -> Result<ActionResult> {
+        Ok(ActionResult::default())
+    }
+
+    /// Whether changes made outside of taskn can be loaded, which isn't the
+    /// case while tasks are being moved or changed.
+    fn allows_reload(&self) -> bool {
+        true
+    }
 }
 
 /// The default interactive mode. Does not modify any data. Allows users to look
@@ -197,10 +1053,12 @@ trait Mode {
 struct Normal;
 
 impl Mode for Normal {
-    fn render(&self, common_state: &mut CommonState, terminal: &mut Term) -> Result<()> {
-        terminal
-            .draw(|frame| common_render(frame, common_state, &[Modifier::DIM]))
-            .context("error drawing terminal")
+    fn name(&self) -> &'static str {
+        "NORMAL"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        common_render(frame, common_state, &[], area);
     }
 
     fn update(
@@ -208,35 +1066,227 @@ impl Mode for Normal {
         _opt: &Opt,
         common_state: &mut CommonState,
         key: Key,
+    ) -> Result<ActionResult> {
+        match common_state.keymap.read(Scope::Normal, key) {
+            Some((command, count)) => Self::run(common_state, command, count),
+            None => Ok(ActionResult::default()),
+        }
+    }
+
+    /// Selects the row under a click, opening it on a double click, and
+    /// scrolls whichever pane is under the wheel. Dragging a task starts
+    /// moving it, as in [Shift].
+    fn mouse(
+        &mut self,
+        opt: &Opt,
+        common_state: &mut CommonState,
+        event: MouseEvent,
+    ) -> Result<ActionResult> {
+        match event {
+            MouseEvent::Press(MouseButton::Left, x, y) => {
+                if let Some(row) = common_state.row_at(x, y) {
+                    common_state.select_entry(&common_state.entries()[row]);
+                    if common_state.click(row) {
+                        return Self::run(common_state, Command::Open, None);
+                    }
+                } else if common_state.preview_at(x, y)
+                    && common_state.selected_task().is_some()
+                {
+                    return Ok(ActionResult {
+                        new_mode: Some(Box::new(Preview)),
+                        ..ActionResult::default()
+                    });
+                }
+            },
+            MouseEvent::Press(button @ (MouseButton::WheelUp | MouseButton::WheelDown), x, y) => {
+                let forward = button == MouseButton::WheelDown;
+                if common_state.row_at(x, y).is_some() {
+                    common_state.move_cursor(forward);
+                } else if common_state.preview_at(x, y) {
+                    common_state.scroll_preview(forward, WHEEL_LINES);
+                }
+            },
+            MouseEvent::Hold(x, y) => {
+                let entries = common_state.entries();
+                let dragged = common_state.row_at(x, y).is_some_and(|row| {
+                    common_state.cursor(&entries) != Some(row)
+                        && common_state.selected_task().is_some()
+                });
+                if dragged {
+                    let mut shift = Shift::new(common_state);
+                    shift.dragging = true;
+                    let result = shift.mouse(opt, common_state, event)?;
+                    return Ok(ActionResult {
+                        new_mode: result.new_mode.or_else(|| Some(Box::new(shift))),
+                        ..result
+                    });
+                }
+            },
+            _ => {},
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+impl Normal {
+    /// Runs a command read from the keymap. Moving takes a count, so `5j`
+    /// moves down five tasks and `5G` goes to the fifth row.
+    fn run(
+        common_state: &mut CommonState,
+        command: Command,
+        count: Option<usize>,
     ) -> Result<ActionResult> {
         let selected = common_state.selected();
-        match key {
-            Key::Up | Key::Char('k' | 'K') =>
-                if selected > 0 {
-                    common_state.list_state.select(Some(selected - 1));
+        match command {
+            Command::Up | Command::Down =>
+                for _ in 0..count.unwrap_or(1) {
+                    common_state.move_cursor(command == Command::Down);
+                },
+            Command::Top | Command::Bottom => match count {
+                Some(row) => {
+                    let entries = common_state.entries();
+                    if let Some(entry) = entries.get(row.min(entries.len()).saturating_sub(1)) {
+                        common_state.select_entry(entry);
+                    }
                 },
-            Key::Down | Key::Char('j' | 'J') =>
-                if selected < common_state.tasks.len() - 1 {
-                    common_state.list_state.select(Some(selected + 1));
+                None => common_state.move_cursor_to_end(command == Command::Bottom),
+            },
+            Command::ToggleTree => {
+                common_state.tree = !common_state.tree;
+                common_state.selected_group = None;
+            },
+            Command::Mark | Command::Open if common_state.selected_group.is_some() =>
+                common_state.toggle_collapsed(),
+            Command::Collapse if common_state.tree => common_state.collapse(),
+            Command::Expand if common_state.tree => common_state.expand(),
+            Command::Filter =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(FilterPrompt::new(common_state))),
+                    ..ActionResult::default()
+                }),
+            Command::Search =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(SearchPrompt::new(common_state))),
+                    ..ActionResult::default()
+                }),
+            Command::ToggleDetail => common_state.detail = !common_state.detail,
+            Command::NextMatch | Command::PreviousMatch =>
+                for _ in 0..count.unwrap_or(1) {
+                    common_state.jump_to_match(command == Command::NextMatch);
                 },
-            Key::Char('g') => common_state.list_state.select(Some(0)),
-            Key::Char('G') => common_state
-                .list_state
-                .select(Some(common_state.tasks.len() - 1)),
-            Key::Char('d') =>
+            Command::Add | Command::AddAndOpen =>
                 return Ok(ActionResult {
-                    new_mode:     Some(Box::new(Done)),
-                    should_flush: false,
-                    should_load:  false,
+                    new_mode: Some(Box::new(AddPrompt::new(command == Command::AddAndOpen))),
+                    ..ActionResult::default()
+                }),
+            Command::Undo =>
+                return Ok(ActionResult {
+                    should_load: common_state.undo()?,
+                    ..ActionResult::default()
+                }),
+            Command::Redo =>
+                return Ok(ActionResult {
+                    should_load: common_state.redo()?,
+                    ..ActionResult::default()
+                }),
+            Command::MarkRange => common_state.toggle_range(),
+            Command::ClearMarks => common_state.clear_marks(),
+            Command::Mark if common_state.selected_task().is_some() => common_state.toggle_mark(),
+            Command::Help =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Help::new(Scope::Normal))),
+                    ..ActionResult::default()
+                }),
+            Command::Quit =>
+                return Ok(ActionResult {
+                    should_quit: true,
+                    ..ActionResult::default()
+                }),
+            // the commands below apply to the marked tasks, or the selected one
+            _ if common_state.targets().is_empty() => {},
+            Command::Done =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Confirm::new(Action::Done, common_state))),
+                    ..ActionResult::default()
+                }),
+            Command::Delete =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Confirm::new(Action::Delete, common_state))),
+                    ..ActionResult::default()
+                }),
+            Command::AddTag | Command::RemoveTag | Command::SetProject | Command::SetWait => {
+                let field = match command {
+                    Command::AddTag => Field::AddTag,
+                    Command::RemoveTag => Field::RemoveTag,
+                    Command::SetProject => Field::Project,
+                    _ => Field::Wait,
+                };
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(BulkPrompt::new(field))),
+                    ..ActionResult::default()
+                });
+            },
+            Command::Run =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(CommandPrompt::new())),
+                    ..ActionResult::default()
+                }),
+            Command::Open =>
+                return Ok(ActionResult {
+                    open_notes: common_state
+                        .targets()
+                        .into_iter()
+                        .map(|index| common_state.tasks[index].clone())
+                        .collect(),
+                    ..ActionResult::default()
                 }),
-            Key::Char('s') =>
+            // everything below acts on the selected task
+            _ if common_state.selected_task().is_none() => {},
+            Command::Shift =>
                 return Ok(ActionResult {
-                    new_mode:     Some(Box::new(Shift::new(selected))),
+                    new_mode:     Some(Box::new(Shift::new(common_state))),
                     should_flush: false,
                     should_load:  false,
+                    ..ActionResult::default()
+                }),
+            Command::FocusPreview =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Preview)),
+                    ..ActionResult::default()
+                }),
+            Command::EditNote =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(NoteEditor::new(
+                        &common_state.tasks[selected],
+                        &common_state.selected_contents(),
+                    ))),
+                    ..ActionResult::default()
                 }),
-            Key::Char('X') => {
-                self.task_edit(common_state);
+            Command::Modify =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Modify::new(&common_state.tasks[selected])?)),
+                    ..ActionResult::default()
+                }),
+            Command::TaskEdit =>
+                return Ok(ActionResult {
+                    edit_task: Some(common_state.tasks[selected].clone()),
+                    ..ActionResult::default()
+                }),
+            Command::Annotate =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(AnnotatePrompt::new(&common_state.tasks[selected]))),
+                    ..ActionResult::default()
+                }),
+            Command::ManageAnnotations => {
+                let task = &common_state.tasks[selected];
+                if task.annotations.as_deref().unwrap_or_default().is_empty() {
+                    common_state.show_message("Task has no annotations");
+                } else {
+                    return Ok(ActionResult {
+                        new_mode: Some(Box::new(Annotations::new(task))),
+                        ..ActionResult::default()
+                    });
+                }
             },
             _ => {},
         }
@@ -244,74 +1294,334 @@ impl Mode for Normal {
             new_mode:     None,
             should_flush: false,
             should_load:  false,
+            ..ActionResult::default()
         })
     }
 }
 
-impl Normal {
-    #[allow(clippy::unused_self)]
-    pub(crate) fn task_edit(self, common_state: &mut CommonState) -> Result<(), Error> {
+/// Allows users to move a selected task (as selected in [Normal] mode) to a
+/// different ordering. Used to modifying the order in which tasks appear in the
+/// default TaskWarrior report. Marked tasks are moved together as a block.
+#[derive(Clone)]
+struct Shift {
+    /// The tasks before any were moved, since moving a task between projects
+    /// in the tree also changes its project
+    original:     Vec<Task>,
+    original_pos: usize,
+    /// The number of tasks being moved, starting at the selected one
+    block:        usize,
+    /// Whether the task is being dragged with the mouse, so that letting go
+    /// of it saves the new order
+    dragging:     bool,
+}
+
+impl Shift {
+    /// Starts moving the selected task, or the marked tasks. Those are
+    /// gathered up where the first of them is.
+    fn new(common_state: &mut CommonState) -> Self {
+        let original = common_state.tasks.clone();
+        let original_pos = common_state.selected();
+        if common_state.anchor.is_some() {
+            common_state.toggle_range();
+        }
+        let marked = common_state.marked_tasks();
+        let block = match marked.first() {
+            Some(start) => {
+                let mut tasks = marked
+                    .iter()
+                    .rev()
+                    .map(|index| common_state.tasks.remove(*index))
+                    .collect::<Vec<_>>();
+                tasks.reverse();
+                common_state.tasks.splice(*start..*start, tasks);
+                common_state.select_task(*start);
+                marked.len()
+            },
+            None => 1,
+        };
+        Self {
+            original,
+            original_pos,
+            block,
+            dragging: false,
+        }
+    }
+
+    /// Moves the selected tasks past the task before (or after) them.
+    fn move_block(&self, common_state: &mut CommonState, forward: bool) {
+        let start = common_state.selected();
+        let end = start + self.block;
+        if forward && end < common_state.tasks.len() {
+            let task = common_state.tasks.remove(end);
+            common_state.tasks.insert(start, task);
+            common_state.select_task(start + 1);
+        } else if !forward && start > 0 {
+            let task = common_state.tasks.remove(start - 1);
+            common_state.tasks.insert(end - 1, task);
+            common_state.select_task(start - 1);
+        }
+    }
+
+    /// Moves the selected task past the next (or previous) row of the tree.
+    /// Tasks within a project are reordered, but when the row belongs to
+    /// another project, returns a prompt to move the task into it.
+    fn move_in_tree(&self, common_state: &mut CommonState, forward: bool) -> Option<Box<dyn Mode>> {
+        let entries = common_state.entries();
+        let position = common_state.cursor(&entries)?;
         let selected = common_state.selected();
+        let project = common_state.tasks[selected].project.clone();
+        let entry_at =
+            |position: Option<usize>| position.and_then(|position| entries.get(position));
 
-        let task_id = common_state.tasks[selected].id;
-        let task_uuid = &common_state.tasks[selected].uuid;
-        let r = Command::new("task").arg(task_uuid).arg("edit").spawn();
-
-        let r = match r {
-            Ok(child) => {
-                let output = child.wait_with_output();
-                match output {
-                    Ok(output) =>
-                        if output.status.success() {
-                            String::from_utf8_lossy(&output.stdout);
-                            Ok(())
-                        } else {
-                            Err(Error::TaskCmd {
-                                command: String::from("edit"),
-                                uuid:    task_uuid.to_string(),
-                                stdout:  String::from_utf8_lossy(&output.stdout).to_string(),
-                                stderr:  String::from_utf8_lossy(&output.stderr).to_string(),
-                            })
-                        },
-                    Err(err) => Err(Error::TaskCmdNoStdout {
-                        command: String::from("edit"),
-                        uuid: task_uuid.to_string(),
-                        err,
-                    }),
+        let neighbour = if forward {
+            entry_at(Some(position + 1))
+        } else {
+            entry_at(position.checked_sub(1))
+        };
+        let target = match neighbour? {
+            Entry::Task { index, .. } if common_state.tasks[*index].project == project => {
+                common_state.tasks.swap(selected, *index);
+                common_state.list_state.select(Some(*index));
+                return None;
+            },
+            Entry::Task { index, .. } => common_state.tasks[*index].project.clone(),
+            // leaving the top of its own project, the task joins whatever is above it
+            Entry::Group(group) if !forward && project.as_ref() == Some(&group.project) =>
+                match entry_at(position.checked_sub(2)) {
+                    Some(Entry::Task { index, .. }) => common_state.tasks[*index].project.clone(),
+                    Some(Entry::Group(group)) => Some(group.project.clone()),
+                    None => None,
+                },
+            Entry::Group(group) => Some(group.project.clone()),
+        };
+        if target == project {
+            return None;
+        }
+        Some(Box::new(ChangeProject {
+            shift: self.clone(),
+            project: target,
+            forward,
+        }))
+    }
+
+    /// Runs a command read from the keymap, moving the tasks `count` times.
+    fn run(&self, common_state: &mut CommonState, command: Command, count: usize) -> ActionResult {
+        match command {
+            Command::Up | Command::Down => {
+                let forward = command == Command::Down;
+                for _ in 0..count {
+                    if common_state.tree && self.block == 1 {
+                        let new_mode = self.move_in_tree(common_state, forward);
+                        if new_mode.is_some() {
+                            return ActionResult {
+                                new_mode,
+                                ..ActionResult::default()
+                            };
+                        }
+                    } else {
+                        self.move_block(common_state, forward);
+                    }
+                }
+            },
+            Command::Save =>
+                return ActionResult {
+                    new_mode:     Some(Box::new(Normal)),
+                    should_flush: true,
+                    should_load:  false,
+                    ..ActionResult::default()
+                },
+            Command::Cancel => {
+                common_state.tasks.clone_from(&self.original);
+                common_state.select_task(self.original_pos);
+                return ActionResult {
+                    new_mode:     Some(Box::new(Normal)),
+                    should_flush: false,
+                    should_load:  false,
+                    ..ActionResult::default()
+                };
+            },
+            _ => {},
+        }
+        ActionResult::default()
+    }
+}
+
+impl Mode for Shift {
+    fn name(&self) -> &'static str {
+        "SHIFT"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        common_render(frame, common_state, &[Modifier::UNDERLINED], area);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match common_state.keymap.read(Scope::Shift, key) {
+            Some((command, count)) => Ok(self.run(common_state, command, count.unwrap_or(1))),
+            None => Ok(ActionResult::default()),
+        }
+    }
+
+    /// Moves a dragged task one row at a time toward the mouse, until it gets
+    /// there or can't go any further, and saves the order once it's dropped.
+    fn mouse(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        event: MouseEvent,
+    ) -> Result<ActionResult> {
+        if !self.dragging {
+            return Ok(ActionResult::default());
+        }
+        match event {
+            MouseEvent::Hold(x, y) => {
+                let target = match common_state.row_at(x, y) {
+                    Some(target) => target,
+                    None => return Ok(ActionResult::default()),
+                };
+                while let Some(cursor) = common_state.cursor(&common_state.entries()) {
+                    if cursor == target {
+                        break;
+                    }
+                    let command = if target > cursor { Command::Down } else { Command::Up };
+                    let result = self.run(common_state, command, 1);
+                    if result.new_mode.is_some() {
+                        return Ok(result);
+                    }
+                    if common_state.cursor(&common_state.entries()) == Some(cursor) {
+                        break;
+                    }
                 }
             },
-            _ => Err(Error::TaskUUID {
-                command: String::from("edit"),
-                uuid:    task_uuid.to_string(),
-            }),
+            MouseEvent::Release(..) => return Ok(self.run(common_state, Command::Save, 1)),
+            MouseEvent::Press(..) => {},
+        }
+        Ok(ActionResult::default())
+    }
+
+    fn allows_reload(&self) -> bool {
+        false
+    }
+}
+
+/// Asks whether a task being moved in [Shift] should change its project, once
+/// it is moved past the edge of its project in the tree.
+struct ChangeProject {
+    shift:   Shift,
+    project: Option<String>,
+    /// Whether the task is moving down, so that it becomes the first task of
+    /// the project rather than the last
+    forward: bool,
+}
+
+impl ChangeProject {
+    fn apply(&self, common_state: &mut CommonState) {
+        let mut task = common_state.tasks.remove(common_state.selected());
+        task.project.clone_from(&self.project);
+        let same_project = common_state
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| other.project == task.project)
+            .map(|(index, _)| index);
+        let position = if self.forward {
+            same_project.min()
+        } else {
+            same_project.max().map(|index| index + 1)
         };
+        let position =
+            position.unwrap_or_else(|| common_state.selected().min(common_state.tasks.len()));
+        common_state.tasks.insert(position, task);
+        common_state.select_task(position);
+    }
+}
+
+impl Mode for ChangeProject {
+    fn name(&self) -> &'static str {
+        "SHIFT"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let layout = default_layout(area);
+        render_tasks(
+            frame,
+            common_state,
+            &[Modifier::DIM, Modifier::UNDERLINED],
+            layout[0],
+        );
+
+        let text = "CONFIRM (ENTER) or CANCEL (ESC)";
+        let title = match &self.project {
+            Some(project) => format!("Move to project {}?", project),
+            None => "Remove from project?".to_string(),
+        };
+        let paragraph = Paragraph::new(text).block(
+            Block::default()
+                .title(title)
+                .style(common_state.theme.dialog)
+                .borders(Borders::ALL),
+        );
+
+        frame.render_widget(paragraph, layout[1]);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Char('\n') => self.apply(common_state),
+            Key::Esc | Key::Ctrl('f') => {},
+            _ => return Ok(ActionResult::default()),
+        }
+        Ok(ActionResult {
+            new_mode: Some(Box::new(self.shift.clone())),
+            ..ActionResult::default()
+        })
+    }
 
-        r
+    fn allows_reload(&self) -> bool {
+        false
     }
 }
 
-/// Allows users to move a selected task (as selected in [Normal] mode) to a
-/// different ordering. Used to modifying the order in which tasks appear in the
-/// default TaskWarrior report.
-struct Shift {
-    original_pos: usize,
+/// Changes the taskwarrior filter selecting which tasks are shown, using the
+/// same syntax as on the command line.
+struct FilterPrompt {
+    input: LineInput,
+    /// Set when the filter could not be parsed
+    error: Option<String>,
 }
 
-impl Shift {
-    fn new(current_pos: usize) -> Self {
+impl FilterPrompt {
+    fn new(common_state: &CommonState) -> Self {
         Self {
-            original_pos: current_pos,
+            input: LineInput::new(&common_state.filter_text()),
+            error: None,
         }
     }
 }
 
-impl Mode for Shift {
-    fn render(&self, common_state: &mut CommonState, terminal: &mut Term) -> Result<()> {
-        terminal
-            .draw(|frame| {
-                common_render(frame, common_state, &[Modifier::DIM, Modifier::UNDERLINED]);
-            })
-            .context("error drawing terminal")
+impl Mode for FilterPrompt {
+    fn name(&self) -> &'static str {
+        "FILTER"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let (main, prompt) = prompt_layout(area);
+        common_render(frame, common_state, &[Modifier::DIM], main);
+        let title = match &self.error {
+            Some(error) => format!("Filter ({})", error),
+            None => "Filter".to_string(),
+        };
+        render_prompt(frame, &common_state.theme, &title, &self.input, prompt);
     }
 
     fn update(
@@ -321,78 +1631,71 @@ impl Mode for Shift {
         key: Key,
     ) -> Result<ActionResult> {
         match key {
-            Key::Up | Key::Char('k' | 'K') => {
-                let selected = common_state.selected();
-                if selected > 0 {
-                    common_state.tasks.swap(selected, selected - 1);
-                    common_state.list_state.select(Some(selected - 1));
-                }
-            },
-            Key::Down | Key::Char('j' | 'J') => {
-                let selected = common_state.selected();
-                if selected < common_state.tasks.len() - 1 {
-                    common_state.tasks.swap(selected, selected + 1);
-                    common_state.list_state.select(Some(selected + 1));
-                }
-            },
-            Key::Char('\n' | 's') =>
-                return Ok(ActionResult {
-                    new_mode:     Some(Box::new(Normal)),
-                    should_flush: true,
-                    should_load:  false,
-                }),
-            Key::Esc | Key::Ctrl('f') => {
-                let selected = common_state.selected();
-                let task = common_state.tasks.remove(selected);
-                common_state.tasks.insert(self.original_pos, task);
-                common_state.list_state.select(Some(self.original_pos));
+            Key::Esc => {
+                common_state.filter_history.reset();
                 return Ok(ActionResult {
-                    new_mode:     Some(Box::new(Normal)),
-                    should_flush: false,
-                    should_load:  false,
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
                 });
             },
-            _ => {},
+            Key::Char('\n') => match shell_words::split(self.input.text()) {
+                Ok(filter) => {
+                    common_state.filter_history.push(self.input.text());
+                    common_state.filter = filter;
+                    common_state.select_task(0);
+                    return Ok(ActionResult {
+                        new_mode: Some(Box::new(Normal)),
+                        should_load: true,
+                        ..ActionResult::default()
+                    });
+                },
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            key =>
+                if common_state.filter_history.handle(key, &mut self.input)
+                    || self.input.handle(key)
+                {
+                    self.error = None;
+                },
         }
+        Ok(ActionResult::default())
+    }
+}
 
-        Ok(ActionResult {
-            new_mode:     None,
-            should_flush: false,
-            should_load:  false,
-        })
+/// Creates a new task from the same arguments as `task add`. The task is
+/// placed at the cursor in the manual order.
+struct AddPrompt {
+    input:     LineInput,
+    /// Whether the new task's note is opened once it is created
+    open_note: bool,
+    /// Set when the task could not be created
+    error:     Option<String>,
+}
+
+impl AddPrompt {
+    fn new(open_note: bool) -> Self {
+        Self {
+            input: LineInput::default(),
+            open_note,
+            error: None,
+        }
     }
 }
 
-/// Marks a task done as
-struct Done;
+impl Mode for AddPrompt {
+    fn name(&self) -> &'static str {
+        "ADD"
+    }
 
-impl Mode for Done {
-    fn render(&self, common_state: &mut CommonState, terminal: &mut Term) -> Result<()> {
-        terminal
-            .draw(|frame| {
-                let layout = default_layout(frame);
-                render_tasks(
-                    frame,
-                    common_state,
-                    &[Modifier::DIM, Modifier::CROSSED_OUT],
-                    layout[0],
-                );
-
-                let text = "CONFIRM (ENTER) or CANCEL (ESC)";
-                let paragraph = Paragraph::new(text).block(
-                    Block::default()
-                        .title("Mark Done?")
-                        .style(
-                            Style::default()
-                                .fg(Color::LightMagenta)
-                                .add_modifier(Modifier::BOLD),
-                        )
-                        .borders(Borders::ALL),
-                );
-
-                frame.render_widget(paragraph, layout[1]);
-            })
-            .context("error drawing terminal")
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let (main, prompt) = prompt_layout(area);
+        common_render(frame, common_state, &[Modifier::DIM], main);
+        let title = match (&self.error, self.open_note) {
+            (Some(error), _) => format!("Add task ({})", error),
+            (None, false) => "Add task".to_string(),
+            (None, true) => "Add task and open its note".to_string(),
+        };
+        render_prompt(frame, &common_state.theme, &title, &self.input, prompt);
     }
 
     fn update(
@@ -402,90 +1705,445 @@ impl Mode for Done {
         key: Key,
     ) -> Result<ActionResult> {
         match key {
-            Key::Esc | Key::Ctrl('f') =>
+            Key::Esc =>
                 return Ok(ActionResult {
-                    new_mode:     Some(Box::new(Normal)),
-                    should_flush: false,
-                    should_load:  false,
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
                 }),
             Key::Char('\n') => {
-                let selected = common_state.selected();
-                common_state.tasks[selected].status = "done".to_string();
-                return Ok(ActionResult {
-                    new_mode:     Some(Box::new(Normal)),
-                    should_flush: true,
-                    should_load:  false,
-                });
+                let task = shell_words::split(self.input.text())
+                    .map_err(anyhow::Error::from)
+                    .and_then(|args| {
+                        if args.is_empty() {
+                            Err(anyhow!("a description is required"))
+                        } else {
+                            Task::add(&args)
+                        }
+                    });
+                match task {
+                    Ok(task) => {
+                        // a new task counts as a deleted one which was brought
+                        // back, so that undoing the addition deletes it again.
+                        // it's already saved, so only its place is flushed
+                        let mut before = task.clone();
+                        before.status = "deleted".to_string();
+                        common_state.record(Change::new(&before, &task).into_iter().collect())?;
+                        common_state.saved.insert(task.uuid.clone(), task.clone());
+                        let position = common_state.selected().min(common_state.tasks.len());
+                        common_state.tasks.insert(position, task.clone());
+                        common_state.select_task(position);
+                        return Ok(ActionResult {
+                            new_mode: Some(Box::new(Normal)),
+                            should_flush: true,
+                            open_notes: if self.open_note { vec![task] } else { vec![] },
+                            ..ActionResult::default()
+                        });
+                    },
+                    Err(e) => self.error = Some(e.to_string()),
+                }
             },
-            _ => {},
+            key =>
+                if self.input.handle(key) {
+                    self.error = None;
+                },
         }
         Ok(ActionResult::default())
     }
 }
 
 // type Frame<'a> = tui::Frame<'a, TermionBackend<RawTerminal<Stdout>>>;
-type Frame<'a> =
-    tui::Frame<'a, TermionBackend<AlternateScreen<MouseTerminal<RawTerminal<Stdout>>>>>;
+type Frame<'a> = tui::Frame<'a, TermionBackend<Output>>;
 
 #[allow(single_use_lifetimes)]
 fn common_render<'a>(
     frame: &mut Frame<'a>,
     common_state: &mut CommonState,
     selected_modifiers: &[Modifier],
+    area: Rect,
 ) {
-    let layout = default_layout(frame);
+    let layout = default_layout(area);
     render_tasks(frame, common_state, selected_modifiers, layout[0]);
-    render_contents(frame, common_state, layout[1]);
+    render_side(frame, common_state, false, layout[1]);
+}
+
+/// Draws the preview, and the detail pane when it's shown.
+#[allow(single_use_lifetimes)]
+fn render_side<'a>(
+    frame: &mut Frame<'a>,
+    common_state: &mut CommonState,
+    preview_focused: bool,
+    area: Rect,
+) {
+    if common_state.detail {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+            .split(area);
+        render_contents(frame, common_state, preview_focused, layout[0]);
+        render_detail(frame, common_state, layout[1]);
+    } else {
+        render_contents(frame, common_state, preview_focused, area);
+    }
 }
 
-fn default_layout(frame: &mut Frame<'_>) -> Vec<Rect> {
+fn default_layout(area: Rect) -> Vec<Rect> {
     Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
-        .split(frame.size())
+        .split(area)
+}
+
+/// Splits off a single line prompt from the bottom of an area.
+fn prompt_layout(area: Rect) -> (Rect, Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+        .split(area);
+    (layout[0], layout[1])
+}
+
+/// Splits off the status bar from the bottom of the screen.
+fn status_layout(area: Rect) -> (Rect, Rect) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(area);
+    (layout[0], layout[1])
+}
+
+/// Shows the mode and the filter, along with the latest message, and on the
+/// right how many tasks there are and how many have changes to be saved.
+fn render_status(frame: &mut Frame<'_>, common_state: &CommonState, mode: &str, area: Rect) {
+    let theme = &common_state.theme;
+    let mut left = vec![Span::styled(format!(" {} ", mode), theme.mode)];
+    if !common_state.filter.is_empty() {
+        left.push(Span::raw(format!(" {}", common_state.filter_text())));
+    }
+    if let Some(message) = &common_state.message {
+        let style = if message.error { theme.error } else { theme.status };
+        left.push(Span::styled(format!(" {}", message.text), style));
+    }
+
+    let shown = common_state
+        .entries()
+        .iter()
+        .filter(|entry| matches!(entry, Entry::Task { .. }))
+        .count();
+    let (pending, waiting) = common_state.counts;
+    let mut right = format!("{} pending, {} waiting, {} shown ", pending, waiting, shown);
+    let unsaved = common_state.unsaved();
+    if unsaved > 0 {
+        right = format!("{} unsaved | {}", unsaved, right);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let width = right.chars().count().min(usize::from(area.width)) as u16;
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(width)].as_ref())
+        .split(area);
+    frame.render_widget(Paragraph::new(Spans::from(left)).style(theme.status), layout[0]);
+    frame.render_widget(Paragraph::new(right).style(theme.status), layout[1]);
+}
+
+#[allow(single_use_lifetimes)]
+fn render_prompt<'a>(
+    frame: &mut Frame<'a>,
+    theme: &Theme,
+    title: &str,
+    input: &LineInput,
+    area: Rect,
+) {
+    let paragraph = Paragraph::new(input.spans()).block(theme.block().title(title));
+    frame.render_widget(paragraph, area);
 }
 
 #[allow(single_use_lifetimes)]
 fn render_tasks<'a>(
     frame: &mut Frame<'a>,
     common_state: &mut CommonState,
-    _selected_modifiers: &[Modifier],
+    selected_modifiers: &[Modifier],
     area: Rect,
 ) {
-    let items: Vec<ListItem> = common_state
-        .tasks
+    let matches = common_state.search_matches();
+    let marked = common_state.marked_tasks();
+    let columns = &common_state.columns;
+    let theme = &common_state.theme;
+    let entries = common_state.entries();
+    let mut table_state = TableState::default();
+    table_state.select(common_state.cursor(&entries));
+    let mut rows = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        match entry {
+            Entry::Task { index, depth } => {
+                let task = &common_state.tasks[*index];
+                let indent = "  ".repeat(*depth);
+                let cells = columns
+                    .iter()
+                    .map(|(column, _)| match column {
+                        Column::Description => format!("{}{}", indent, task.description),
+                        column => column.text(task, common_state),
+                    })
+                    .collect::<Vec<_>>();
+                let found = matches.iter().find(|m| m.index == *index);
+                let mut style = theme.task_style(task);
+                if marked.contains(index) {
+                    style = style.patch(theme.marked);
+                }
+                rows.push((cells, found.map(|m| (m, indent.len())), style));
+            },
+            Entry::Group(group) => {
+                let cells = columns
+                    .iter()
+                    .map(|(column, _)| match column {
+                        Column::Description => format!(
+                            "{}{} {} ({} task{}, urgency {:.1})",
+                            "  ".repeat(group.depth),
+                            if group.collapsed { '▸' } else { '▾' },
+                            group.name(),
+                            group.count,
+                            if group.count == 1 { "" } else { "s" },
+                            group.urgency
+                        ),
+                        _ => String::new(),
+                    })
+                    .collect::<Vec<_>>();
+                rows.push((cells, None, theme.group));
+            },
+        }
+    }
+
+    // leave room for the borders and the highlight symbol
+    let cells = rows.iter().map(|(cells, ..)| cells.as_slice()).collect::<Vec<_>>();
+    let widths = columns::widths(columns, &cells, usize::from(area.width).saturating_sub(4));
+
+    let rows = rows
+        .into_iter()
+        .map(|(cells, found, style)| {
+            let row = Row::new(cells.into_iter().zip(columns.iter().zip(&widths)).map(
+                |(text, ((column, _), width))| {
+                    if *column != Column::Description {
+                        return Cell::from(columns::truncate(&text, *width));
+                    }
+                    // the characters matching the search are underlined
+                    let truncated = columns::truncate(&text, *width);
+                    let spans = truncated
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| {
+                            let matched = found.is_some_and(|(m, indent)| {
+                                i >= indent && m.description.contains(&(i - indent))
+                            });
+                            if matched {
+                                Span::styled(c.to_string(), theme.matched)
+                            } else {
+                                Span::raw(c.to_string())
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    Cell::from(Spans::from(spans))
+                },
+            ));
+            row.style(style)
+        })
+        .collect::<Vec<_>>();
+    let header = Row::new(columns.iter().map(|(_, label)| label.clone()))
+        .style(theme.header);
+    #[allow(clippy::cast_possible_truncation)]
+    let constraints = widths
         .iter()
-        .map(|task| ListItem::new(task.description.as_str()))
-        .collect();
-
-    // let mut highlight_style = Style::default();
-    // for modifier in selected_modifiers.iter() {
-    //     highlight_style = highlight_style.add_modifier(*modifier);
-    // }
-
-    let list = List::new(items)
-        .block(Block::default().title("Tasks").borders(Borders::ALL))
-        .highlight_style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        )
+        .map(|width| Constraint::Length((*width).min(usize::from(u16::MAX)) as u16))
+        .collect::<Vec<_>>();
+
+    // the mode tells whether the list is focused, e.g. dimming it behind a
+    // prompt or underlining the tasks being moved
+    let highlight_style = selected_modifiers
+        .iter()
+        .fold(theme.selected, |style, modifier| style.add_modifier(*modifier));
+
+    let title = if common_state.filter.is_empty() {
+        "Tasks".to_string()
+    } else {
+        format!("Tasks ({})", common_state.filter_text())
+    };
+    let title = match common_state.search.as_ref() {
+        Some(search) if !search.query.is_empty() =>
+            format!("{} /{} [{} matches]", title, search.query, matches.len()),
+        _ => title,
+    };
+    let title = if marked.is_empty() {
+        title
+    } else {
+        format!("{} [{} marked]", title, marked.len())
+    };
+
+    let table = Table::new(rows)
+        .header(header)
+        .widths(&constraints)
+        .block(theme.block().title(title))
+        .highlight_style(highlight_style)
         .highlight_symbol("> ");
 
+    // the rows start below the borders and the header, and the table scrolls
+    // just far enough to show the selected row
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    common_state.list_area = Rect {
+        y:      inner.y + 1,
+        height: inner.height.saturating_sub(1),
+        ..inner
+    };
+    common_state.list_offset = table_state.selected().map_or(0, |selected| {
+        (selected + 1).saturating_sub(usize::from(common_state.list_area.height))
+    });
+
     // frame.render_widget(header, chunks[0]);
-    frame.render_stateful_widget(list, area, &mut common_state.list_state);
+    frame.render_stateful_widget(table, area, &mut table_state);
 }
 
 #[allow(single_use_lifetimes)]
-fn render_contents<'a>(frame: &mut Frame<'a>, common_state: &mut CommonState, area: Rect) {
+fn render_contents<'a>(
+    frame: &mut Frame<'a>,
+    common_state: &mut CommonState,
+    focused: bool,
+    area: Rect,
+) {
     // preview the current highlighted task's notes
-    let contents = common_state.selected_contents();
-    let paragraph = Paragraph::new(contents).block(
-        Block::default()
-            .title("Preview")
-            .style(Style::default().fg(Color::Red))
-            .borders(Borders::ALL),
+    let mut block = Block::default()
+        .border_style(common_state.theme.preview_border)
+        .borders(Borders::ALL);
+    if focused {
+        block = block.border_type(BorderType::Thick);
+    }
+    let inner = block.inner(area);
+    let rendered = markup::render(
+        &common_state.selected_contents(),
+        common_state.format,
+        usize::from(inner.width),
+        &common_state.theme,
     );
+    let mut text = Text::from(rendered.lines);
+
+    let annotations = common_state
+        .selected_task()
+        .and_then(|task| task.annotations.as_deref())
+        .unwrap_or_default();
+    if !annotations.is_empty() {
+        text.extend([
+            Spans::default(),
+            Spans::from(Span::styled("Annotations", common_state.theme.label)),
+        ]);
+        text.extend(annotations.iter().map(|annotation| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("  {:>8} ", annotations::ago(annotation)),
+                    common_state.theme.hint,
+                ),
+                Span::raw(annotation.description.clone()),
+            ])
+        }));
+    }
+
+    let backlinks = common_state.selected_backlinks();
+    if !backlinks.is_empty() {
+        text.extend([
+            Spans::default(),
+            Spans::from(Span::styled("Linked from", common_state.theme.label)),
+        ]);
+        text.extend(
+            backlinks
+                .into_iter()
+                .map(|description| Spans::from(format!("  - {}", description))),
+        );
+    }
+
+    common_state.preview = PreviewState {
+        area,
+        lines:    text.height(),
+        height:   usize::from(inner.height),
+        headings: rendered.headings,
+    };
+    // the note may have shrunk since it was scrolled
+    let scroll = common_state.preview_scroll();
+    common_state.set_preview_scroll(scroll);
+    let scroll = common_state.preview_scroll();
+
+    let title = if common_state.preview.lines > common_state.preview.height {
+        format!(
+            "Preview [{}-{}/{}]",
+            scroll + 1,
+            (scroll + common_state.preview.height).min(common_state.preview.lines),
+            common_state.preview.lines
+        )
+    } else {
+        "Preview".to_string()
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let paragraph = Paragraph::new(text)
+        .block(block.title(title))
+        .scroll((scroll.min(usize::from(u16::MAX)) as u16, 0));
+
+    frame.render_widget(paragraph, area);
+}
+
+/// Shows every attribute of the selected task, including the ones taskn
+/// doesn't otherwise use, and its annotations.
+#[allow(single_use_lifetimes)]
+fn render_detail<'a>(frame: &mut Frame<'a>, common_state: &mut CommonState, area: Rect) {
+    let theme = &common_state.theme;
+    let mut lines = vec![];
+    if let Some(task) = common_state.selected_task() {
+        let date = |date: &Option<ParsableDateTime>| {
+            date.as_ref()
+                .map(|date| date.0.format("%Y-%m-%d %H:%M").to_string())
+        };
+        let attributes = vec![
+            ("id", Some(task.id.to_string())),
+            ("uuid", Some(task.uuid.clone())),
+            ("status", Some(task.status.clone())),
+            ("description", Some(task.description.clone())),
+            ("project", task.project.clone()),
+            ("tags", task.tags.as_ref().map(|tags| tags.join(" "))),
+            ("priority", task.priority.clone()),
+            ("due", date(&task.due)),
+            ("wait", date(&task.wait)),
+            ("scheduled", date(&task.scheduled)),
+            ("entry", date(&task.entry)),
+            ("modified", date(&task.modified)),
+            ("urgency", task.urgency.map(|urgency| format!("{:.2}", urgency))),
+            ("estimate", task.estimate.clone()),
+        ];
+        let extra = task.extra.iter().map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            (name.as_str(), Some(value))
+        });
+        for (name, value) in attributes.into_iter().chain(extra) {
+            if let Some(value) = value {
+                lines.push(Spans::from(vec![
+                    Span::styled(format!("{:>12} ", name), theme.label),
+                    Span::raw(value),
+                ]));
+            }
+        }
+
+        let annotations = task.annotations.as_deref().unwrap_or_default();
+        if !annotations.is_empty() {
+            lines.push(Spans::default());
+            lines.push(Spans::from(Span::styled("Annotations", theme.label)));
+            lines.extend(annotations.iter().map(|annotation| {
+                Spans::from(format!(
+                    "  {} {}",
+                    annotation.entry.0.format("%Y-%m-%d %H:%M"),
+                    annotation.description
+                ))
+            }));
+        }
+    }
 
+    let paragraph = Paragraph::new(lines)
+        .block(theme.block().title("Detail"))
+        .wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
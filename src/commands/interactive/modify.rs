@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use termion::event::Key;
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Span, Spans},
+    widgets::Paragraph,
+};
+
+use super::{
+    common_render,
+    conflict::Resolve,
+    input::{Completion, LineInput},
+    ActionResult, CommonState, Frame, Mode, Normal,
+};
+use crate::{
+    opt::Opt,
+    taskwarrior::{self, ParsableDateTime, Task},
+};
+
+/// The labels of the attributes edited in [Modify], in the order they are
+/// shown
+const FIELDS: [&str; 7] = [
+    "Description",
+    "Project",
+    "Tags",
+    "Priority",
+    "Due",
+    "Wait",
+    "Scheduled",
+];
+
+const DESCRIPTION: usize = 0;
+const PROJECT: usize = 1;
+const TAGS: usize = 2;
+const PRIORITY: usize = 3;
+const DUE: usize = 4;
+const WAIT: usize = 5;
+const SCHEDULED: usize = 6;
+
+/// Edits the attributes of the selected task in a form. Only that task is
+/// saved to taskwarrior.
+pub(crate) struct Modify {
+    task:       Task,
+    inputs:     Vec<LineInput>,
+    /// The text each field started with, so that unchanged dates are not
+    /// evaluated again
+    original:   Vec<String>,
+    focus:      usize,
+    projects:   Vec<String>,
+    tags:       Vec<String>,
+    completion: Option<Completion>,
+    error:      Option<String>,
+}
+
+impl Modify {
+    pub(crate) fn new(task: &Task) -> Result<Self> {
+        let date = |date: &Option<ParsableDateTime>| {
+            date.as_ref()
+                .map(|date| {
+                    let date = date.to_taskwarrior();
+                    // midnight is left off, since it's what taskwarrior assumes
+                    date.strip_suffix("T00:00:00").map_or(date.clone(), String::from)
+                })
+                .unwrap_or_default()
+        };
+        let original = vec![
+            task.description.clone(),
+            task.project.clone().unwrap_or_default(),
+            task.tags.as_deref().unwrap_or_default().join(" "),
+            task.priority.clone().unwrap_or_default(),
+            date(&task.due),
+            date(&task.wait),
+            date(&task.scheduled),
+        ];
+
+        Ok(Self {
+            task: task.clone(),
+            inputs: original.iter().map(|text| LineInput::new(text)).collect(),
+            original,
+            focus: DESCRIPTION,
+            projects: taskwarrior::projects().context("error listing projects")?,
+            tags: taskwarrior::tags().context("error listing tags")?,
+            completion: None,
+            error: None,
+        })
+    }
+
+    /// Builds the modified task from the form. On failure, returns the field
+    /// at fault along with the reason.
+    fn apply(&self) -> Result<Task, (usize, String)> {
+        let text = |field: usize| self.inputs[field].text().trim();
+        let mut task = self.task.clone();
+
+        if text(DESCRIPTION).is_empty() {
+            return Err((DESCRIPTION, "a description is required".to_string()));
+        }
+        task.description = text(DESCRIPTION).to_string();
+        task.project = Some(text(PROJECT).to_string()).filter(|project| !project.is_empty());
+
+        let tags = text(TAGS)
+            .split_whitespace()
+            .map(|tag| tag.trim_start_matches('+').to_string())
+            .collect::<Vec<_>>();
+        task.tags = Some(tags).filter(|tags| !tags.is_empty());
+
+        task.priority = match text(PRIORITY).to_uppercase().as_str() {
+            "" => None,
+            priority @ ("H" | "M" | "L") => Some(priority.to_string()),
+            _ => return Err((PRIORITY, "priority must be H, M or L".to_string())),
+        };
+
+        for field in [DUE, WAIT, SCHEDULED] {
+            if text(field) == self.original[field] {
+                continue;
+            }
+            let date = if text(field).is_empty() {
+                None
+            } else {
+                Some(taskwarrior::calc_date(text(field)).map_err(|e| (field, e.to_string()))?)
+            };
+            match field {
+                DUE => task.due = date,
+                WAIT => task.wait = date,
+                _ => task.scheduled = date,
+            }
+        }
+        Ok(task)
+    }
+
+    /// Completes the project, or the tag under the cursor, stepping through
+    /// candidates when there are several. Returns false when there is nothing
+    /// (left) to complete, so that `Tab` can move on to the next field.
+    fn complete(&mut self) -> bool {
+        if let Some(completion) = &mut self.completion {
+            if completion.is_last() {
+                return false;
+            }
+            completion.next();
+        } else {
+            let text = self.inputs[self.focus].text();
+            let (before, word, known) = match self.focus {
+                PROJECT => ("", text, &self.projects),
+                TAGS => {
+                    let start = text.rfind(' ').map_or(0, |space| space + 1);
+                    (&text[..start], &text[start..], &self.tags)
+                },
+                _ => return false,
+            };
+            self.completion = Completion::new(before, word.trim_start_matches('+'), known);
+        }
+
+        match &self.completion {
+            Some(completion) => {
+                self.inputs[self.focus].set(&completion.line());
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl Mode for Modify {
+    fn name(&self) -> &'static str {
+        "MODIFY"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        #[allow(clippy::cast_possible_truncation)]
+        let height = FIELDS.len() as u16 + 3;
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(height)].as_ref())
+            .split(area);
+        common_render(frame, common_state, &[Modifier::DIM], layout[0]);
+
+        let mut lines = FIELDS
+            .iter()
+            .zip(&self.inputs)
+            .enumerate()
+            .map(|(field, (label, input))| {
+                let label = format!("{:>12}: ", label);
+                if field == self.focus {
+                    let mut spans = input.spans();
+                    spans.0.insert(0, Span::styled(label, common_state.theme.label));
+                    spans
+                } else {
+                    Spans::from(vec![Span::raw(label), Span::raw(input.text().to_string())])
+                }
+            })
+            .collect::<Vec<_>>();
+        lines.push(Spans::from(match &self.completion {
+            Some(completion) => completion.candidates.join(" "),
+            None => "TAB complete, ENTER save, ESC cancel".to_string(),
+        }));
+
+        let title = match &self.error {
+            Some(error) => format!("Modify {} ({})", self.task.id, error),
+            None => format!("Modify {}", self.task.id),
+        };
+        let form = Paragraph::new(lines).block(common_state.theme.block().title(title));
+        frame.render_widget(form, layout[1]);
+    }
+
+    fn update(
+        &mut self,
+        opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        if key != Key::Char('\t') {
+            self.completion = None;
+        }
+        match key {
+            Key::Esc =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                }),
+            Key::Char('\n') => match self.apply() {
+                Ok(task) => {
+                    let conflicts =
+                        common_state.save_unless_changed(opt, vec![(self.task.clone(), task)])?;
+                    let new_mode: Box<dyn Mode> = if conflicts.is_empty() {
+                        Box::new(Normal)
+                    } else {
+                        Box::new(Resolve::new(conflicts))
+                    };
+                    return Ok(ActionResult {
+                        new_mode: Some(new_mode),
+                        should_load: true,
+                        ..ActionResult::default()
+                    });
+                },
+                Err((field, error)) => {
+                    self.focus = field;
+                    self.error = Some(format!("{}: {}", FIELDS[field].to_lowercase(), error));
+                },
+            },
+            Key::Char('\t') if matches!(self.focus, PROJECT | TAGS) && self.complete() => {},
+            Key::Char('\t') | Key::Down => {
+                self.completion = None;
+                self.focus = (self.focus + 1) % FIELDS.len();
+            },
+            Key::BackTab | Key::Up =>
+                self.focus = (self.focus + FIELDS.len() - 1) % FIELDS.len(),
+            key =>
+                if self.inputs[self.focus].handle(key) {
+                    self.error = None;
+                },
+        }
+        Ok(ActionResult::default())
+    }
+
+    fn allows_reload(&self) -> bool {
+        false
+    }
+}
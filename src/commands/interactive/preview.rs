@@ -0,0 +1,233 @@
+use anyhow::Result;
+use termion::event::{Key, MouseButton, MouseEvent};
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    widgets::{Clear, List, ListItem, ListState, Paragraph},
+};
+
+use super::{
+    default_layout,
+    input::LineInput,
+    keymap::{Command, Help, Scope},
+    markup::Heading,
+    render_side, render_tasks, ActionResult, CommonState, Frame, Mode, Normal, WHEEL_LINES,
+};
+use crate::opt::Opt;
+
+/// The preview as it was last drawn, which is what scrolling is measured
+/// against
+#[derive(Default)]
+pub(crate) struct PreviewState {
+    /// Where the pane was drawn, to tell when the mouse is over it
+    pub(crate) area:     Rect,
+    /// The number of lines in the preview, after wrapping
+    pub(crate) lines:    usize,
+    /// The number of lines which fit in the pane
+    pub(crate) height:   usize,
+    pub(crate) headings: Vec<Heading>,
+}
+
+/// Scrolls through the selected task's note, once the preview is focused with
+/// `Tab`.
+pub(crate) struct Preview;
+
+impl Mode for Preview {
+    fn name(&self) -> &'static str {
+        "PREVIEW"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let layout = default_layout(area);
+        render_tasks(frame, common_state, &[Modifier::DIM], layout[0]);
+        render_side(frame, common_state, true, layout[1]);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        let (command, count) = match common_state.keymap.read(Scope::Preview, key) {
+            Some(found) => found,
+            None => return Ok(ActionResult::default()),
+        };
+        let scroll = common_state.preview_scroll();
+        let page = common_state.preview.height.max(1);
+        let lines = count.unwrap_or(1);
+        let new_mode: Box<dyn Mode> = match command {
+            Command::Back => Box::new(Normal),
+            Command::Outline => Box::new(Outline::new(common_state)),
+            Command::Help => Box::new(Help::new(Scope::Preview)),
+            Command::Quit =>
+                return Ok(ActionResult {
+                    should_quit: true,
+                    ..ActionResult::default()
+                }),
+            command => {
+                let scroll = match command {
+                    Command::Down => scroll + lines,
+                    Command::Up => scroll.saturating_sub(lines),
+                    Command::HalfPageDown => scroll + lines * page / 2,
+                    Command::HalfPageUp => scroll.saturating_sub(lines * page / 2),
+                    Command::PageDown => scroll + lines * page,
+                    Command::PageUp => scroll.saturating_sub(lines * page),
+                    // a count goes to that line, as in `5G`
+                    Command::Top | Command::Bottom if count.is_some() => lines - 1,
+                    Command::Top => 0,
+                    Command::Bottom => usize::MAX,
+                    _ => scroll,
+                };
+                common_state.set_preview_scroll(scroll);
+                return Ok(ActionResult::default());
+            },
+        };
+        Ok(ActionResult {
+            new_mode: Some(new_mode),
+            ..ActionResult::default()
+        })
+    }
+
+    /// Scrolls the preview with the wheel. Clicking the task list goes back to
+    /// it, as with `Tab`.
+    fn mouse(
+        &mut self,
+        opt: &Opt,
+        common_state: &mut CommonState,
+        event: MouseEvent,
+    ) -> Result<ActionResult> {
+        match event {
+            MouseEvent::Press(button @ (MouseButton::WheelUp | MouseButton::WheelDown), x, y)
+                if common_state.preview_at(x, y) =>
+                common_state.scroll_preview(button == MouseButton::WheelDown, WHEEL_LINES),
+            MouseEvent::Press(MouseButton::Left, x, y) if common_state.row_at(x, y).is_some() => {
+                let result = Normal.mouse(opt, common_state, event)?;
+                return Ok(ActionResult {
+                    new_mode: result.new_mode.or_else(|| Some(Box::new(Normal))),
+                    ..result
+                });
+            },
+            _ => {},
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+/// Lists the headings of the selected task's note, narrowed by what is typed,
+/// and scrolls the preview to the chosen one.
+pub(crate) struct Outline {
+    headings: Vec<Heading>,
+    input:    LineInput,
+    /// The selected heading, among the ones matching the input
+    selected: usize,
+}
+
+impl Outline {
+    fn new(common_state: &CommonState) -> Self {
+        Self {
+            headings: common_state.preview.headings.clone(),
+            input:    LineInput::default(),
+            selected: 0,
+        }
+    }
+
+    fn matching(&self) -> Vec<&Heading> {
+        let query = self.input.text().to_lowercase();
+        self.headings
+            .iter()
+            .filter(|heading| heading.title.to_lowercase().contains(&query))
+            .collect()
+    }
+}
+
+impl Mode for Outline {
+    fn name(&self) -> &'static str {
+        "OUTLINE"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let layout = default_layout(area);
+        render_tasks(frame, common_state, &[Modifier::DIM], layout[0]);
+        render_side(frame, common_state, true, layout[1]);
+
+        let matching = self.matching();
+        let items = matching
+            .iter()
+            .map(|heading| {
+                ListItem::new(format!(
+                    "{}{}",
+                    "  ".repeat(heading.level.saturating_sub(1)),
+                    heading.title
+                ))
+            })
+            .collect::<Vec<_>>();
+        let empty = items.is_empty();
+        let mut list_state = ListState::default();
+        list_state.select(Some(self.selected).filter(|_| !empty));
+
+        #[allow(clippy::cast_possible_truncation)]
+        let height = (matching.len().max(1) + 5).min(usize::from(layout[1].height)) as u16;
+        let area = popup(layout[1], height);
+        let popup = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .split(area);
+        frame.render_widget(Clear, area);
+        let title = if empty { "Outline (no headings)" } else { "Outline" };
+        let list = List::new(items)
+            .block(common_state.theme.block().title(title))
+            .highlight_style(common_state.theme.selected)
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, popup[0], &mut list_state);
+        let input = Paragraph::new(self.input.spans())
+            .block(common_state.theme.block().title("Jump to"));
+        frame.render_widget(input, popup[1]);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        let count = self.matching().len();
+        match key {
+            Key::Esc =>
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Preview)),
+                    ..ActionResult::default()
+                }),
+            Key::Char('\n') => {
+                if let Some(line) = self.matching().get(self.selected).map(|heading| heading.line) {
+                    common_state.set_preview_scroll(line);
+                }
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Preview)),
+                    ..ActionResult::default()
+                });
+            },
+            Key::Up | Key::Ctrl('p') => self.selected = self.selected.saturating_sub(1),
+            Key::Down | Key::Ctrl('n') =>
+                if self.selected + 1 < count {
+                    self.selected += 1;
+                },
+            key =>
+                if self.input.handle(key) {
+                    self.selected = 0;
+                },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+/// A centered area of the given height, inset from the sides of an area.
+pub(crate) fn popup(area: Rect, height: u16) -> Rect {
+    let width = area.width.saturating_sub(8).max(area.width.min(20));
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
+}
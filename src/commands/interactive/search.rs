@@ -0,0 +1,256 @@
+use anyhow::Result;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use termion::event::Key;
+use tui::{layout::Rect, style::Modifier};
+
+use super::{
+    common_render, input::LineInput, prompt_layout, render_prompt, ActionResult, CommonState,
+    Frame, Mode, Normal,
+};
+use crate::{opt::Opt, taskwarrior::Task};
+
+/// A fuzzy search through the tasks being shown. The tasks themselves, and
+/// their order, are never changed by searching.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Search {
+    pub(crate) query:         String,
+    /// Whether note contents are searched as well
+    pub(crate) include_notes: bool,
+    /// Whether only matching tasks are shown
+    pub(crate) narrow:        bool,
+}
+
+/// A task which matches a [Search]
+pub(crate) struct Match {
+    /// The index of the task in [CommonState::tasks]
+    pub(crate) index:       usize,
+    score:                  i64,
+    /// The indices of the characters of the description which matched
+    pub(crate) description: Vec<usize>,
+}
+
+impl Search {
+    /// Finds the tasks matching the search, in the order they are listed.
+    /// Descriptions, projects, tags and (optionally) notes, as read by
+    /// `contents_of` from a task's UUID, are searched.
+    pub(crate) fn matches<F: Fn(&str) -> String>(
+        &self,
+        tasks: &[Task],
+        contents_of: F,
+    ) -> Vec<Match> {
+        if self.query.is_empty() {
+            return vec![];
+        }
+        let matcher = SkimMatcherV2::default().smart_case();
+        tasks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, task)| {
+                let (mut score, description) = matcher
+                    .fuzzy_indices(&task.description, &self.query)
+                    .map_or((None, vec![]), |(score, indices)| (Some(score), indices));
+
+                let mut fields = vec![task.project.clone().unwrap_or_default()];
+                fields.extend(task.tags.iter().flatten().cloned());
+                if self.include_notes {
+                    fields.push(contents_of(&task.uuid));
+                }
+                for field in &fields {
+                    if let Some(field_score) = matcher.fuzzy_match(field, &self.query) {
+                        score = score.max(Some(field_score));
+                    }
+                }
+
+                score.map(|score| Match {
+                    index,
+                    score,
+                    description,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Searches through tasks as a query is typed, narrowing the list to the
+/// matching tasks. Once the search is confirmed every task is shown again,
+/// with `n` and `N` jumping between the matches.
+pub(crate) struct SearchPrompt {
+    input: LineInput,
+    /// The task selected before searching, returned to when cancelling
+    original_selected: usize,
+}
+
+impl SearchPrompt {
+    pub(crate) fn new(common_state: &mut CommonState) -> Self {
+        let search = common_state.search.get_or_insert_with(Search::default);
+        search.narrow = true;
+        Self {
+            input: LineInput::new(&search.query),
+            original_selected: common_state.selected(),
+        }
+    }
+
+    fn title(common_state: &CommonState) -> String {
+        let notes = common_state
+            .search
+            .as_ref()
+            .is_some_and(|search| search.include_notes);
+        format!(
+            "Search{} (TAB {} notes)",
+            if notes { " tasks and notes" } else { "" },
+            if notes { "skips" } else { "includes" }
+        )
+    }
+
+    /// Moves the selection to the best match, or keeps it when it is still a
+    /// match.
+    fn select_best(common_state: &mut CommonState) {
+        let matches = common_state.search_matches();
+        let selected = common_state.selected();
+        if matches.iter().any(|m| m.index == selected) {
+            return;
+        }
+        if let Some(best) = matches.iter().max_by_key(|m| m.score) {
+            common_state.select_task(best.index);
+        }
+    }
+}
+
+impl Mode for SearchPrompt {
+    fn name(&self) -> &'static str {
+        "SEARCH"
+    }
+
+    fn render(&self, common_state: &mut CommonState, frame: &mut Frame<'_>, area: Rect) {
+        let title = Self::title(common_state);
+        let (main, prompt) = prompt_layout(area);
+        common_render(frame, common_state, &[Modifier::DIM], main);
+        render_prompt(frame, &common_state.theme, &title, &self.input, prompt);
+    }
+
+    fn update(
+        &mut self,
+        _opt: &Opt,
+        common_state: &mut CommonState,
+        key: Key,
+    ) -> Result<ActionResult> {
+        match key {
+            Key::Esc => {
+                common_state.search = None;
+                common_state.select_task(self.original_selected);
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                });
+            },
+            Key::Char('\n') => {
+                if self.input.text().is_empty() {
+                    common_state.search = None;
+                } else if let Some(search) = &mut common_state.search {
+                    search.narrow = false;
+                }
+                return Ok(ActionResult {
+                    new_mode: Some(Box::new(Normal)),
+                    ..ActionResult::default()
+                });
+            },
+            Key::Char('\t') => {
+                if let Some(search) = &mut common_state.search {
+                    search.include_notes = !search.include_notes;
+                }
+                Self::select_best(common_state);
+            },
+            Key::Up => common_state.jump_to_match(false),
+            Key::Down => common_state.jump_to_match(true),
+            key =>
+                if self.input.handle(key) {
+                    if let Some(search) = &mut common_state.search {
+                        search.query = self.input.text().to_string();
+                    }
+                    Self::select_best(common_state);
+                },
+        }
+        Ok(ActionResult::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: usize, description: &str) -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "uuid": format!("{:08}-0000-0000-0000-000000000000", id),
+            "description": description,
+            "status": "pending",
+            "project": "work.api",
+            "tags": ["urgent"],
+        }))
+        .unwrap()
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![task(1, "Write the report"), task(2, "Review the API")]
+    }
+
+    /// The indices of the tasks matching a query
+    fn matching(query: &str, include_notes: bool) -> Vec<usize> {
+        let search = Search {
+            query: query.to_string(),
+            include_notes,
+            narrow: false,
+        };
+        let notes = |uuid: &str| {
+            if uuid.starts_with("00000002") {
+                "mention the changelog".to_string()
+            } else {
+                String::new()
+            }
+        };
+        search
+            .matches(&tasks(), notes)
+            .into_iter()
+            .map(|m| m.index)
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_subsequence() {
+        assert_eq!(matching("wtr", false), vec![0]);
+        assert_eq!(matching("the", false), vec![0, 1]);
+        // the characters have to come in order
+        assert_eq!(matching("tropw", false), Vec::<usize>::new());
+        assert_eq!(matching("xyz", false), Vec::<usize>::new());
+
+        let search = Search {
+            query: "report".to_string(),
+            ..Search::default()
+        };
+        let matches = search.matches(&tasks(), |_| String::new());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].description, vec![10, 11, 12, 13, 14, 15]);
+    }
+
+    #[test]
+    fn test_matches_case() {
+        // a lowercase query ignores case, but capitals in it have to match
+        assert_eq!(matching("api", false), vec![0, 1]);
+        assert_eq!(matching("API", false), vec![1]);
+        assert_eq!(matching("write", false), vec![0]);
+        assert_eq!(matching("WRITE", false), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_matches_fields() {
+        assert_eq!(matching("urgent", false), vec![0, 1]);
+        assert_eq!(matching("changelog", false), Vec::<usize>::new());
+        assert_eq!(matching("changelog", true), vec![1]);
+    }
+
+    #[test]
+    fn test_matches_empty_query() {
+        assert_eq!(matching("", false), Vec::<usize>::new());
+        assert_eq!(matching("", true), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,445 @@
+use std::{env, str::FromStr};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Local};
+use tui::{
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders},
+};
+
+use crate::{
+    config,
+    taskwarrior::{self, Task},
+};
+
+/// The built-in themes, which the config file's `colors` are laid on top of
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Base {
+    Dark,
+    Light,
+    /// Only bold, underlined and reversed text, for when colors aren't wanted
+    NoColor,
+}
+
+impl FromStr for Base {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dark" => Ok(Self::Dark),
+            "light" => Ok(Self::Light),
+            "none" | "no-color" => Ok(Self::NoColor),
+            _ => Err(format!("unknown theme '{}'", s)),
+        }
+    }
+}
+
+/// How each part of the interactive view is drawn
+#[derive(Clone, Debug)]
+pub(crate) struct Theme {
+    /// The borders of the panes and prompts
+    pub(crate) border:         Style,
+    pub(crate) preview_border: Style,
+    /// The row under the cursor, along with the modifiers of the mode
+    pub(crate) selected:       Style,
+    pub(crate) marked:         Style,
+    /// The rows of projects in the tree
+    pub(crate) group:          Style,
+    pub(crate) header:         Style,
+    /// The characters matching a search
+    pub(crate) matched:        Style,
+    /// Boxes asking to confirm something
+    pub(crate) dialog:         Style,
+    /// The names of attributes and fields
+    pub(crate) label:          Style,
+    /// Text explaining what a key or an action does
+    pub(crate) hint:           Style,
+    /// The bar at the bottom of the screen, and the name of the mode in it
+    pub(crate) status:         Style,
+    pub(crate) mode:           Style,
+    /// Messages about actions which failed
+    pub(crate) error:          Style,
+    /// Tasks which were started
+    pub(crate) active:         Style,
+    pub(crate) overdue:        Style,
+    /// Tasks due within taskwarrior's `due` setting
+    pub(crate) due:            Style,
+    pub(crate) tagged:         Style,
+    pub(crate) heading1:       Style,
+    pub(crate) heading2:       Style,
+    /// The headings below the second level
+    pub(crate) heading:        Style,
+    pub(crate) code:           Style,
+    pub(crate) link:           Style,
+    /// Text which is only there for reference, like rules, quote bars and
+    /// the targets of links
+    pub(crate) faint:          Style,
+    pub(crate) checked:        Style,
+    /// Checkboxes which are partly done, as in `[-]`
+    pub(crate) partial:        Style,
+    /// The syntect theme highlighting code blocks, if they are highlighted
+    pub(crate) highlighting:   Option<&'static str>,
+    /// How many days ahead a task counts as due
+    due_days:                  i64,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(Base::Dark)
+    }
+}
+
+impl Theme {
+    pub(crate) fn new(base: Base) -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let underlined = plain.add_modifier(Modifier::UNDERLINED);
+        let dim = plain.add_modifier(Modifier::DIM);
+        let common = Self {
+            border:         plain,
+            preview_border: plain.fg(Color::Red),
+            selected:       bold.fg(Color::Yellow),
+            marked:         plain.fg(Color::Cyan),
+            group:          bold,
+            header:         bold,
+            matched:        underlined,
+            dialog:         bold.fg(Color::LightMagenta),
+            label:          bold,
+            hint:           dim,
+            status:         plain.bg(Color::DarkGray),
+            mode:           bold.fg(Color::Black).bg(Color::Yellow),
+            error:          bold.fg(Color::LightRed).bg(Color::DarkGray),
+            active:         plain,
+            overdue:        plain,
+            due:            plain,
+            tagged:         plain,
+            heading1:       bold.fg(Color::Magenta).add_modifier(Modifier::UNDERLINED),
+            heading2:       bold.fg(Color::Cyan),
+            heading:        bold,
+            code:           plain.fg(Color::Yellow),
+            link:           underlined.fg(Color::Blue),
+            faint:          plain.fg(Color::DarkGray),
+            checked:        plain.fg(Color::Green),
+            partial:        plain.fg(Color::Yellow),
+            highlighting:   Some("base16-ocean.dark"),
+            due_days:       7,
+        };
+        match base {
+            Base::Dark => common,
+            Base::Light => Self {
+                selected: bold.fg(Color::Blue),
+                marked: plain.fg(Color::Magenta),
+                status: plain.bg(Color::Gray),
+                mode: bold.fg(Color::White).bg(Color::Blue),
+                error: bold.fg(Color::Red).bg(Color::Gray),
+                dialog: bold.fg(Color::Magenta),
+                heading2: bold.fg(Color::Blue),
+                code: plain.fg(Color::Red),
+                partial: plain.fg(Color::Magenta),
+                highlighting: Some("base16-ocean.light"),
+                ..common
+            },
+            Base::NoColor => Self {
+                preview_border: plain,
+                selected: bold.add_modifier(Modifier::REVERSED),
+                marked: plain.add_modifier(Modifier::ITALIC),
+                status: plain.add_modifier(Modifier::REVERSED),
+                mode: bold,
+                error: bold.add_modifier(Modifier::REVERSED),
+                dialog: bold,
+                heading1: bold.add_modifier(Modifier::UNDERLINED),
+                heading2: bold,
+                code: plain,
+                link: underlined,
+                faint: dim,
+                checked: plain,
+                partial: plain,
+                highlighting: None,
+                ..common
+            },
+        }
+    }
+
+    /// The style of an element, by its name in the config file.
+    fn element(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "border" => &mut self.border,
+            "preview_border" => &mut self.preview_border,
+            "selected" => &mut self.selected,
+            "marked" => &mut self.marked,
+            "group" => &mut self.group,
+            "header" => &mut self.header,
+            "match" => &mut self.matched,
+            "dialog" => &mut self.dialog,
+            "label" => &mut self.label,
+            "hint" => &mut self.hint,
+            "status" => &mut self.status,
+            "mode" => &mut self.mode,
+            "error" => &mut self.error,
+            "active" => &mut self.active,
+            "overdue" => &mut self.overdue,
+            "due" => &mut self.due,
+            "tagged" => &mut self.tagged,
+            "heading1" => &mut self.heading1,
+            "heading2" => &mut self.heading2,
+            "heading" => &mut self.heading,
+            "code" => &mut self.code,
+            "link" => &mut self.link,
+            "faint" => &mut self.faint,
+            "checked" => &mut self.checked,
+            "partial" => &mut self.partial,
+            _ => return None,
+        })
+    }
+
+    /// A bordered block, as every pane and prompt is drawn in.
+    pub(crate) fn block<'a>(&self) -> Block<'a> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.border)
+    }
+
+    pub(crate) fn heading_style(&self, level: usize) -> Style {
+        match level {
+            1 => self.heading1,
+            2 => self.heading2,
+            _ => self.heading,
+        }
+    }
+
+    /// The style of a task's row, following taskwarrior's precedence of
+    /// active, then overdue, then due, then tagged.
+    pub(crate) fn task_style(&self, task: &Task) -> Style {
+        let now = Local::now();
+        let due = task.due.as_ref().map(|due| due.0);
+        if task.extra.contains_key("start") {
+            self.active
+        } else if due.is_some_and(|due| due < now) {
+            self.overdue
+        } else if due.is_some_and(|due| due < now + Duration::days(self.due_days)) {
+            self.due
+        } else if task.tags.as_ref().is_some_and(|tags| !tags.is_empty()) {
+            self.tagged
+        } else {
+            Style::default()
+        }
+    }
+
+    /// Takes the colors of tasks from taskwarrior's `color.*` settings.
+    fn import_taskwarrior(&mut self) -> Result<()> {
+        let setting = |name: &str| {
+            taskwarrior::config_value(name)
+                .with_context(|| format!("error reading '{}' from taskwarrior", name))
+        };
+        if ["off", "no", "false", "0"].contains(&setting("color")?.as_str()) {
+            return Ok(());
+        }
+        for name in ["active", "overdue", "due", "tagged"] {
+            let value = setting(&format!("color.{}", name))?;
+            let style = parse_style(&value)
+                .map_err(|e| anyhow!("{} in taskwarrior's color.{}", e, name))?;
+            if let Some(element) = self.element(name) {
+                *element = style;
+            }
+        }
+        if let Ok(days) = setting("due")?.parse() {
+            self.due_days = days;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the theme from the config file. Without a `theme` there, setting
+/// `$NO_COLOR` picks the theme without colors.
+pub(crate) fn from_config(config: &config::Interactive) -> Result<Theme> {
+    let base = match &config.theme {
+        Some(name) => name.parse::<Base>().map_err(|e| anyhow!("{} in config", e))?,
+        None if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) =>
+            Base::NoColor,
+        None => Base::Dark,
+    };
+    let mut theme = Theme::new(base);
+    if config.taskwarrior_colors {
+        theme.import_taskwarrior()?;
+    }
+    for (name, value) in &config.colors {
+        let style = parse_style(value).map_err(|e| anyhow!("{} for '{}' in config", e, name))?;
+        let element = theme
+            .element(name)
+            .ok_or_else(|| anyhow!("unknown element '{}' in config colors", name))?;
+        *element = style;
+    }
+    Ok(theme)
+}
+
+/// Parses a style the way taskwarrior writes colors, e.g. `bold red on
+/// bright blue`, `color214`, `rgb035 on gray4` or `#ff8800`.
+pub(crate) fn parse_style(s: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut background = false;
+    let mut bright = false;
+    for word in s.split_whitespace() {
+        let modifier = match word.to_ascii_lowercase().as_str() {
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underline" => Modifier::UNDERLINED,
+            "inverse" | "reverse" => Modifier::REVERSED,
+            "blink" => Modifier::SLOW_BLINK,
+            "on" => {
+                background = true;
+                continue;
+            },
+            "bright" => {
+                bright = true;
+                continue;
+            },
+            word => {
+                let color = parse_color(word, bright)?;
+                style = if background {
+                    style.bg(color)
+                } else {
+                    style.fg(color)
+                };
+                bright = false;
+                continue;
+            },
+        };
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
+
+fn parse_color(word: &str, bright: bool) -> Result<Color, String> {
+    let invalid = || format!("invalid color '{}'", word);
+    let named = match (word, bright) {
+        ("black", false) => Some(Color::Black),
+        ("red", false) => Some(Color::Red),
+        ("green", false) => Some(Color::Green),
+        ("yellow", false) => Some(Color::Yellow),
+        ("blue", false) => Some(Color::Blue),
+        ("magenta", false) => Some(Color::Magenta),
+        ("cyan", false) => Some(Color::Cyan),
+        ("white", false) => Some(Color::Gray),
+        ("black", true) => Some(Color::DarkGray),
+        ("red", true) => Some(Color::LightRed),
+        ("green", true) => Some(Color::LightGreen),
+        ("yellow", true) => Some(Color::LightYellow),
+        ("blue", true) => Some(Color::LightBlue),
+        ("magenta", true) => Some(Color::LightMagenta),
+        ("cyan", true) => Some(Color::LightCyan),
+        ("white", true) => Some(Color::White),
+        _ => None,
+    };
+    if let Some(color) = named {
+        return Ok(color);
+    }
+
+    let number = |digits: &str, max: u8| digits.parse::<u8>().ok().filter(|n| *n <= max);
+    if let Some(index) = word.strip_prefix("color") {
+        return number(index, u8::MAX).map(Color::Indexed).ok_or_else(invalid);
+    }
+    if let Some(level) = word.strip_prefix("gray").or_else(|| word.strip_prefix("grey")) {
+        return number(level, 23)
+            .map(|level| Color::Indexed(232 + level))
+            .ok_or_else(invalid);
+    }
+    if let Some(rgb) = word.strip_prefix("rgb") {
+        let levels = rgb
+            .chars()
+            .map(|c| number(&c.to_string(), 5))
+            .collect::<Option<Vec<_>>>();
+        return match levels.as_deref() {
+            Some([r, g, b]) => Ok(Color::Indexed(16 + 36 * r + 6 * g + b)),
+            _ => Err(invalid()),
+        };
+    }
+    if let Some(hex) = word.strip_prefix('#').filter(|hex| hex.len() == 6) {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match (channel(0), channel(2), channel(4)) {
+            (Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+            _ => Err(invalid()),
+        };
+    }
+    Err(invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        let cases = [
+            ("red", false, Color::Red),
+            ("white", false, Color::Gray),
+            ("red", true, Color::LightRed),
+            ("black", true, Color::DarkGray),
+            ("white", true, Color::White),
+            ("color0", false, Color::Indexed(0)),
+            ("color214", false, Color::Indexed(214)),
+            ("color255", false, Color::Indexed(255)),
+            ("gray0", false, Color::Indexed(232)),
+            ("grey23", false, Color::Indexed(255)),
+            ("rgb000", false, Color::Indexed(16)),
+            ("rgb035", false, Color::Indexed(16 + 6 * 3 + 5)),
+            ("rgb555", false, Color::Indexed(231)),
+            ("#ff8800", false, Color::Rgb(0xff, 0x88, 0x00)),
+        ];
+        for (word, bright, color) in &cases {
+            assert_eq!(parse_color(word, *bright), Ok(*color), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_parse_color_invalid() {
+        let cases = [
+            "purple", "color", "color256", "color-1", "gray24", "grey", "rgb", "rgb12", "rgb1234",
+            "rgb600", "#ff880", "#ff88000", "#gg8800",
+        ];
+        for word in &cases {
+            assert_eq!(parse_color(word, false), Err(format!("invalid color '{}'", word)));
+        }
+    }
+
+    #[test]
+    fn test_parse_style() {
+        let cases = [
+            ("", Style::default()),
+            ("red", Style::default().fg(Color::Red)),
+            ("RED", Style::default().fg(Color::Red)),
+            ("on blue", Style::default().bg(Color::Blue)),
+            (
+                "bold red on bright blue",
+                Style::default()
+                    .fg(Color::Red)
+                    .bg(Color::LightBlue)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            ("bright red on blue", Style::default().fg(Color::LightRed).bg(Color::Blue)),
+            (
+                "underline italic color214",
+                Style::default()
+                    .fg(Color::Indexed(214))
+                    .add_modifier(Modifier::UNDERLINED | Modifier::ITALIC),
+            ),
+            (
+                "dim inverse blink",
+                Style::default()
+                    .add_modifier(Modifier::DIM | Modifier::REVERSED | Modifier::SLOW_BLINK),
+            ),
+            ("reverse", Style::default().add_modifier(Modifier::REVERSED)),
+            ("rgb035 on gray4", Style::default().fg(Color::Indexed(39)).bg(Color::Indexed(236))),
+        ];
+        for (s, style) in &cases {
+            assert_eq!(parse_style(s), Ok(*style), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_parse_style_invalid() {
+        assert_eq!(parse_style("bold purple"), Err("invalid color 'purple'".to_string()));
+        assert_eq!(parse_style("on color300"), Err("invalid color 'color300'".to_string()));
+        assert_eq!(parse_style("bright #12345"), Err("invalid color '#12345'".to_string()));
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::taskwarrior::Task;
+
+/// A row of the task list
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Entry {
+    /// The header of a project, in the tree view
+    Group(Group),
+    /// A task, by its index in [super::CommonState::tasks], along with how
+    /// deeply it is nested in the tree
+    Task { index: usize, depth: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Group {
+    /// The full name of the project, e.g. `work.api`
+    pub(crate) project:   String,
+    pub(crate) depth:     usize,
+    /// The number of tasks in the project and its subprojects
+    pub(crate) count:     usize,
+    /// The sum of the urgency of those tasks
+    pub(crate) urgency:   f64,
+    pub(crate) collapsed: bool,
+}
+
+impl Group {
+    /// The last part of the project's name, e.g. `api` for `work.api`.
+    pub(crate) fn name(&self) -> &str {
+        self.project.rsplit('.').next().unwrap_or_default()
+    }
+}
+
+/// A project in the tree, holding the indices of its own tasks
+#[derive(Default)]
+struct Node {
+    tasks:    Vec<usize>,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn count(&self) -> usize {
+        self.tasks.len() + self.children.values().map(Node::count).sum::<usize>()
+    }
+
+    fn urgency(&self, tasks: &[Task]) -> f64 {
+        let own = self
+            .tasks
+            .iter()
+            .filter_map(|index| tasks[*index].urgency)
+            .sum::<f64>();
+        own + self
+            .children
+            .values()
+            .map(|child| child.urgency(tasks))
+            .sum::<f64>()
+    }
+
+    fn flatten(
+        &self,
+        path: &str,
+        depth: usize,
+        tasks: &[Task],
+        collapsed: &HashSet<String>,
+        entries: &mut Vec<Entry>,
+    ) {
+        entries.extend(
+            self.tasks
+                .iter()
+                .map(|index| Entry::Task { index: *index, depth }),
+        );
+        for (name, child) in &self.children {
+            let project = if path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}.{}", path, name)
+            };
+            let is_collapsed = collapsed.contains(&project);
+            entries.push(Entry::Group(Group {
+                project: project.clone(),
+                depth,
+                count: child.count(),
+                urgency: child.urgency(tasks),
+                collapsed: is_collapsed,
+            }));
+            if !is_collapsed {
+                child.flatten(&project, depth + 1, tasks, collapsed, entries);
+            }
+        }
+    }
+}
+
+/// Groups the shown tasks by project. Each project is a header followed by
+/// its own tasks, in their manual order, and then its subprojects. Tasks
+/// without a project come first.
+pub(crate) fn entries(tasks: &[Task], shown: &[usize], collapsed: &HashSet<String>) -> Vec<Entry> {
+    let mut root = Node::default();
+    for index in shown {
+        let mut node = &mut root;
+        if let Some(project) = &tasks[*index].project {
+            for part in project.split('.') {
+                node = node.children.entry(part.to_string()).or_default();
+            }
+        }
+        node.tasks.push(*index);
+    }
+
+    let mut entries = vec![];
+    root.flatten("", 0, tasks, collapsed, &mut entries);
+    entries
+}
+
+/// The projects containing a project, from the outermost one, and then the
+/// project itself, e.g. `work` and `work.api` for `work.api`.
+pub(crate) fn ancestors(project: &str) -> Vec<String> {
+    project
+        .match_indices('.')
+        .map(|(end, _)| project[..end].to_string())
+        .chain(Some(project.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: usize, project: Option<&str>, urgency: f64) -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "uuid": format!("{:08}-0000-0000-0000-000000000000", id),
+            "description": "Write the report",
+            "status": "pending",
+            "project": project,
+            "urgency": urgency,
+        }))
+        .unwrap()
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            task(1, None, 1.0),
+            task(2, Some("work.api"), 2.0),
+            task(3, Some("work"), 3.0),
+            task(4, Some("home"), 0.5),
+            task(5, None, 1.0),
+        ]
+    }
+
+    fn group(project: &str, depth: usize, count: usize, urgency: f64, collapsed: bool) -> Entry {
+        Entry::Group(Group {
+            project: project.to_string(),
+            depth,
+            count,
+            urgency,
+            collapsed,
+        })
+    }
+
+    #[test]
+    fn test_entries() {
+        let entries = entries(&tasks(), &[0, 1, 2, 3, 4], &HashSet::new());
+        assert_eq!(entries, vec![
+            Entry::Task { index: 0, depth: 0 },
+            Entry::Task { index: 4, depth: 0 },
+            group("home", 0, 1, 0.5, false),
+            Entry::Task { index: 3, depth: 1 },
+            group("work", 0, 2, 5.0, false),
+            Entry::Task { index: 2, depth: 1 },
+            group("work.api", 1, 1, 2.0, false),
+            Entry::Task { index: 1, depth: 2 },
+        ]);
+    }
+
+    #[test]
+    fn test_entries_only_counts_shown_tasks() {
+        let entries = entries(&tasks(), &[1], &HashSet::new());
+        assert_eq!(entries, vec![
+            group("work", 0, 1, 2.0, false),
+            group("work.api", 1, 1, 2.0, false),
+            Entry::Task { index: 1, depth: 2 },
+        ]);
+        assert!(super::entries(&tasks(), &[], &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_entries_collapsed() {
+        let collapsed = vec!["work".to_string()].into_iter().collect();
+        let entries = entries(&tasks(), &[0, 1, 2, 3], &collapsed);
+        assert_eq!(entries, vec![
+            Entry::Task { index: 0, depth: 0 },
+            group("home", 0, 1, 0.5, false),
+            Entry::Task { index: 3, depth: 1 },
+            group("work", 0, 2, 5.0, true),
+        ]);
+
+        let collapsed = vec!["work.api".to_string()].into_iter().collect();
+        let entries = super::entries(&tasks(), &[1, 2], &collapsed);
+        assert_eq!(entries, vec![
+            group("work", 0, 2, 5.0, false),
+            Entry::Task { index: 2, depth: 1 },
+            group("work.api", 1, 1, 2.0, true),
+        ]);
+    }
+
+    #[test]
+    fn test_group_name() {
+        let name = |project: &str| match group(project, 0, 0, 0.0, false) {
+            Entry::Group(group) => group.name().to_string(),
+            Entry::Task { .. } => unreachable!(),
+        };
+        assert_eq!(name("work.api"), "api");
+        assert_eq!(name("home"), "home");
+    }
+
+    #[test]
+    fn test_ancestors() {
+        assert_eq!(ancestors("work.api.v2"), vec!["work", "work.api", "work.api.v2"]);
+        assert_eq!(ancestors("home"), vec!["home"]);
+    }
+}
@@ -0,0 +1,14 @@
+use anyhow::{anyhow, Result};
+
+use crate::{git, notes::Resolver, opt::Opt, taskwarrior::Task};
+
+/// Shows the git history of a task's note.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let filter = match opt.args.as_slice() {
+        [filter] => filter,
+        _ => return Err(anyhow!("usage: taskn log <id>")),
+    };
+    let task = Task::get_single(filter)?;
+    git::log(opt, &Resolver::new(opt)?.note_path(&task))?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{
+    git,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskn_warning,
+    taskwarrior::Task,
+};
+
+/// Moves every note into the layout selected by `--layout` and the naming
+/// scheme selected by `--naming`, encrypting or decrypting it to match
+/// `--encrypt`. Directories holding only attachments are renamed along with
+/// the notes.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let tasks = Task::get(std::iter::empty::<&str>())?;
+    let mut resolver = Resolver::new(opt)?;
+    let mut keys = resolver
+        .notes()
+        .into_iter()
+        .chain(resolver.dirs())
+        .map(|(key, _)| key.to_string())
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    let mut migrated = 0;
+    for key in keys {
+        let task = match tasks.iter().find(|task| notes::key_matches(&key, &task.uuid)) {
+            Some(task) => task,
+            None => {
+                taskn_warning!("{}: no task owns this note, leaving it in place", key);
+                continue;
+            },
+        };
+        // the directory goes first, so that a flat note can be moved into it
+        let moved = resolver.relocate_dir(task).and_then(|dir| {
+            let note = resolver.relocate(task, opt.layout, opt.encrypt)?;
+            Ok(dir.is_some() || note.is_some())
+        });
+        match moved {
+            Ok(true) => migrated += 1,
+            Ok(false) => {},
+            Err(e) => taskn_warning!("{}: {}", key, e),
+        }
+    }
+
+    if migrated > 0 && opt.git {
+        git::init(opt)?;
+        git::commit(opt, &format!("Migrate {} notes", migrated))?;
+    }
+
+    println!("{} {} notes", "migrated".green(), migrated);
+    Ok(())
+}
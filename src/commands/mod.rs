@@ -1,7 +1,16 @@
+pub(crate) mod attach;
+pub(crate) mod attachments;
+pub(crate) mod backlinks;
+pub(crate) mod check_links;
 pub(crate) mod edit;
 pub(crate) mod interactive;
+pub(crate) mod log;
+pub(crate) mod migrate;
 pub(crate) mod order;
-#[cfg(target = "macos")]
+pub(crate) mod restore;
+pub(crate) mod sync;
+pub(crate) mod undo;
+#[cfg(target_os = "macos")]
 pub(crate) mod remind;
 
 use std::str::FromStr;
@@ -11,25 +20,38 @@ use clap::Clap;
 use crate::opt::Opt;
 
 /// Available subcommands
-#[derive(Clap, Debug, Clone, PartialEq, Copy)]
+#[derive(Clap, Debug, Clone, PartialEq, Copy, Default)]
 pub(crate) enum Command {
     /// Edit or create the `taskn` notes
+    #[default]
     Edit,
     /// Open an interactive viewer of `task` reminders
     Interactive,
     /// WTF?
     Order,
+    /// List the notes which link to the selected tasks
+    Backlinks,
+    /// Report links in notes which point to deleted or missing tasks
+    CheckLinks,
+    /// Copy files into a task's directory: `attach <id> <files...>`
+    Attach,
+    /// List the attachments of the selected tasks
+    Attachments,
+    /// Move every note into the layout selected by `--layout` and `--encrypt`
+    Migrate,
+    /// Show the git history of a task's note: `log <id>`
+    Log,
+    /// Bring back a past version of a task's note: `restore <id> <rev>`
+    Restore,
+    /// Pull and push the notes with the remote given by `--git-remote`
+    Sync,
+    /// Revert the last change taskn made to tasks
+    Undo,
     /// Set a reminder on `macOS`
-    #[cfg(target = "macos")]
+    #[cfg(target_os = "macos")]
     Remind,
 }
 
-impl Default for Command {
-    fn default() -> Self {
-        Self::Edit
-    }
-}
-
 impl Command {
     /// Does the main work of the program by executing each subcommand with its options
     pub(crate) fn execute(self, opt: &Opt) -> Result<()> {
@@ -37,7 +59,16 @@ impl Command {
             Self::Edit => edit::execute(opt),
             Self::Interactive => interactive::execute(opt),
             Self::Order => order::execute(opt),
-            #[cfg(target = "macos")]
+            Self::Backlinks => backlinks::execute(opt),
+            Self::CheckLinks => check_links::execute(opt),
+            Self::Attach => attach::execute(opt),
+            Self::Attachments => attachments::execute(opt),
+            Self::Migrate => migrate::execute(opt),
+            Self::Log => log::execute(opt),
+            Self::Restore => restore::execute(opt),
+            Self::Sync => sync::execute(opt),
+            Self::Undo => undo::execute(opt),
+            #[cfg(target_os = "macos")]
             Self::Remind => remind::execute(opt),
         }
     }
@@ -51,7 +82,16 @@ impl FromStr for Command {
             "edit" => Ok(Self::Edit),
             "interactive" => Ok(Self::Interactive),
             "order" => Ok(Self::Order),
-            #[cfg(target = "macos")]
+            "backlinks" => Ok(Self::Backlinks),
+            "check-links" => Ok(Self::CheckLinks),
+            "attach" => Ok(Self::Attach),
+            "attachments" => Ok(Self::Attachments),
+            "migrate" => Ok(Self::Migrate),
+            "log" => Ok(Self::Log),
+            "restore" => Ok(Self::Restore),
+            "sync" => Ok(Self::Sync),
+            "undo" => Ok(Self::Undo),
+            #[cfg(target_os = "macos")]
             "remind" => Ok(Self::Remind),
             _ => Err(format!("failed to parse command from '{}'", s)),
         }
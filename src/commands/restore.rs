@@ -0,0 +1,45 @@
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+
+use super::edit::sync_tag;
+use crate::{
+    crypt, git,
+    notes::{self, Resolver},
+    opt::Opt,
+    taskwarrior::Task,
+};
+
+/// Restores a task's note to its contents at a past git revision.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    let (filter, rev) = match opt.args.as_slice() {
+        [filter, rev] => (filter, rev),
+        _ => return Err(anyhow!("usage: taskn restore <id> <rev>")),
+    };
+    let task = Task::get_single(filter)?;
+    let mut resolver = Resolver::new(opt)?;
+    let path = resolver.note_path(&task);
+
+    // the note may have been encrypted (or not) under another name back then
+    let (old_path, contents) = git::show(opt, &path, rev)?;
+    let contents = if crypt::is_encrypted(&old_path) {
+        crypt::decrypt(opt, &old_path, &contents)?
+    } else {
+        contents
+    };
+    let contents = String::from_utf8(contents)
+        .with_context(|| format!("{} at {} isn't valid UTF-8", old_path.display(), rev))?;
+    // with `--encrypt`, preparing the note may encrypt it under a new path
+    resolver.prepare(&task)?;
+    let path = resolver.note_path(&task);
+    notes::write(opt, &path, &contents)?;
+    println!("{} {} to {}", "restored".green(), path.display(), rev);
+
+    sync_tag(&resolver, &task)?;
+    if git::is_repository(opt) {
+        git::commit(
+            opt,
+            &format!("Restore {}: {} to {}", task.id, task.description, rev),
+        )?;
+    }
+    Ok(())
+}
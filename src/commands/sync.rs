@@ -0,0 +1,11 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{git, opt::Opt};
+
+/// Pulls notes from the git remote and pushes local history to it.
+pub(crate) fn execute(opt: &Opt) -> Result<()> {
+    git::sync(opt)?;
+    println!("{} with {}", "synced".green(), opt.git_remote.as_deref().unwrap_or("remote"));
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::{journal::Journal, opt::Opt};
+
+/// Reverts the last change taskn made to tasks, even from an interactive
+/// session which has since exited.
+pub(crate) fn execute(_opt: &Opt) -> Result<()> {
+    let journal = Journal::open();
+    let operation = match journal.pop()? {
+        Some(operation) => operation,
+        None => {
+            println!("{}", "nothing to undo".dimmed());
+            return Ok(());
+        },
+    };
+    if let Err(err) = operation.undo() {
+        // keep the operation so that undoing can be tried again
+        journal.push(&operation)?;
+        return Err(err.into());
+    }
+    println!("{} {}", "undid".green(), operation.summary);
+    Ok(())
+}
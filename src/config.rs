@@ -0,0 +1,133 @@
+//! The taskn configuration file.
+//!
+//! Settings which are too detailed for command line flags, like the layout of
+//! the interactive view, are read from a TOML file. The file is found at
+//! `--config`, or otherwise at `$XDG_CONFIG_HOME/taskn/config.toml`, and
+//! every setting in it is optional.
+
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::opt::Opt;
+
+/// Errors used within this file
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    /// Error reading the configuration file
+    #[error("failed to read config '{path}': {err}")]
+    Read {
+        path: String,
+        #[source]
+        err:  io::Error,
+    },
+    /// The configuration file is not valid
+    #[error("invalid config '{path}': {err}")]
+    Parse {
+        path: String,
+        #[source]
+        err:  toml::de::Error,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) interactive: Interactive,
+}
+
+/// Settings for `taskn interactive`
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Interactive {
+    /// The columns of the task list, e.g. `["id", "project", "description"]`
+    pub(crate) columns:            Vec<String>,
+    /// Headers for each of the columns, replacing their default names
+    pub(crate) labels:             Vec<String>,
+    /// A taskwarrior report whose `columns` and `labels` are used instead
+    pub(crate) report:             Option<String>,
+    /// Whether the detail pane is shown when starting
+    pub(crate) detail:             bool,
+    /// Whether tasks are grouped by project when starting
+    pub(crate) tree:               bool,
+    /// Whether the mouse can be used to select, scroll and reorder tasks
+    pub(crate) mouse:              bool,
+    /// Keys bound on top of the defaults
+    pub(crate) keys:               Keys,
+    /// The built-in theme: `dark`, `light` or `none`
+    pub(crate) theme:              Option<String>,
+    /// Styles of parts of the view, replacing the theme's, e.g.
+    /// `selected = "bold yellow"`
+    pub(crate) colors:             BTreeMap<String, String>,
+    /// Whether tasks are colored by taskwarrior's `color.*` settings
+    pub(crate) taskwarrior_colors: bool,
+}
+
+impl Default for Interactive {
+    fn default() -> Self {
+        Self {
+            columns:            ["id", "project", "description"]
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            labels:             vec![],
+            report:             None,
+            detail:             false,
+            tree:               false,
+            mouse:              true,
+            keys:               Keys::default(),
+            theme:              None,
+            colors:             BTreeMap::new(),
+            taskwarrior_colors: false,
+        }
+    }
+}
+
+/// Key sequences bound to commands in each mode of the interactive view, e.g.
+/// `gg = "top"`, or `"<C-d>" = "none"` to unbind a key
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Keys {
+    pub(crate) normal:  BTreeMap<String, String>,
+    pub(crate) preview: BTreeMap<String, String>,
+    pub(crate) shift:   BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Reads the configuration file. A missing file is only an error when it
+    /// was given with `--config`.
+    pub(crate) fn load(opt: &Opt) -> Result<Self, Error> {
+        let path = match &opt.config {
+            Some(path) => PathBuf::from(path),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        Self::read(&path)
+    }
+
+    fn read(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|err| Error::Read {
+            path: path.display().to_string(),
+            err,
+        })?;
+        toml::from_str(&contents).map_err(|err| Error::Parse {
+            path: path.display().to_string(),
+            err,
+        })
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(dir.join("taskn").join("config.toml"))
+}
@@ -0,0 +1,352 @@
+//! Encryption of notes at rest.
+//!
+//! Encrypted notes are [age](https://age-encryption.org) files encrypted to
+//! an X25519 identity. The identity is either read from a key file given with
+//! `--identity`, or is stored inside of the root directory, itself encrypted
+//! with a passphrase. The passphrase is read from `$TASKN_PASSPHRASE`, or is
+//! prompted for once per run.
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Read, Write},
+    iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use age::{
+    secrecy::{ExposeSecret, SecretString},
+    x25519, Decryptor, Encryptor, IdentityFile, IdentityFileEntry,
+};
+use colored::Colorize;
+use termion::input::TermRead;
+use thiserror::Error;
+
+use crate::opt::Opt;
+
+/// The extension added onto the name of encrypted notes
+pub(crate) const EXTENSION: &str = "age";
+
+/// The name of the passphrase protected identity inside of the root directory
+pub(crate) const KEY_NAME: &str = ".taskn-key.age";
+
+/// The environment variable which may hold the passphrase
+const PASSPHRASE_VAR: &str = "TASKN_PASSPHRASE";
+
+/// The identity used for the rest of the run, once it has been unlocked
+static IDENTITY: OnceLock<x25519::Identity> = OnceLock::new();
+
+/// Errors used within this file
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    /// The key file does not hold a usable identity
+    #[error("'{0}' does not contain an age X25519 identity")]
+    InvalidIdentity(String),
+    /// No passphrase was given
+    #[error("a passphrase is required to unlock encrypted notes")]
+    NoPassphrase,
+    /// The passphrases entered when creating a key differ
+    #[error("passphrases do not match")]
+    PassphraseMismatch,
+    /// Error encrypting a note
+    #[error("failed to encrypt note: {0}")]
+    Encrypt(#[source] age::EncryptError),
+    /// Error decrypting a note, or the passphrase protected identity
+    #[error("failed to decrypt '{path}': {err}")]
+    Decrypt {
+        path: String,
+        #[source]
+        err:  age::DecryptError,
+    },
+    /// General IO error
+    #[error("IO error: {0}")]
+    IO(#[source] io::Error),
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::IO(err) => err,
+            err => io::Error::other(err),
+        }
+    }
+}
+
+/// Determines whether a note is stored encrypted, based on its path.
+pub(crate) fn is_encrypted(path: &Path) -> bool {
+    path.extension() == Some(EXTENSION.as_ref())
+}
+
+/// Adds the encrypted extension onto the path of a note.
+pub(crate) fn encrypted_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Unlocks the identity used to encrypt and decrypt notes, prompting for a
+/// passphrase if required. Later calls reuse the identity.
+pub(crate) fn unlock(opt: &Opt) -> Result<&'static x25519::Identity, Error> {
+    if let Some(identity) = IDENTITY.get() {
+        return Ok(identity);
+    }
+    let identity = match &opt.identity {
+        Some(path) => read_identity_file(path)?,
+        None => read_protected_identity(opt)?,
+    };
+    Ok(IDENTITY.get_or_init(|| identity))
+}
+
+fn read_identity_file(path: &str) -> Result<x25519::Identity, Error> {
+    IdentityFile::from_file(path.to_string())
+        .map_err(Error::IO)?
+        .into_identities()
+        .into_iter()
+        .find_map(|entry| match entry {
+            IdentityFileEntry::Native(identity) => Some(identity),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        })
+        .ok_or_else(|| Error::InvalidIdentity(path.to_string()))
+}
+
+/// Reads the identity stored inside of the root directory, creating it with a
+/// new passphrase if it does not exist yet.
+fn read_protected_identity(opt: &Opt) -> Result<x25519::Identity, Error> {
+    let key_path = Path::new(&opt.root_dir).join(KEY_NAME);
+    if !key_path.exists() {
+        let passphrase = passphrase("New passphrase for taskn notes: ")?;
+        if env::var(PASSPHRASE_VAR).is_err()
+            && passphrase.expose_secret()
+                != self::passphrase("Confirm passphrase: ")?.expose_secret()
+        {
+            return Err(Error::PassphraseMismatch);
+        }
+
+        let identity = x25519::Identity::generate();
+        let mut encrypted = vec![];
+        let mut writer = Encryptor::with_user_passphrase(passphrase)
+            .wrap_output(&mut encrypted)
+            .map_err(Error::Encrypt)?;
+        writer
+            .write_all(identity.to_string().expose_secret().as_bytes())
+            .map_err(Error::IO)?;
+        writer.finish().map_err(Error::IO)?;
+
+        fs::create_dir_all(&opt.root_dir).map_err(Error::IO)?;
+        write_private(&key_path, &encrypted)?;
+        return Ok(identity);
+    }
+
+    let decrypt_error = |err| Error::Decrypt {
+        path: key_path.display().to_string(),
+        err,
+    };
+    let encrypted = fs::read(&key_path).map_err(Error::IO)?;
+    let mut contents = String::new();
+    match Decryptor::new(&encrypted[..]).map_err(decrypt_error)? {
+        Decryptor::Passphrase(decryptor) => decryptor
+            .decrypt(&passphrase("Passphrase for taskn notes: ")?, None)
+            .map_err(decrypt_error)?
+            .read_to_string(&mut contents)
+            .map_err(Error::IO)?,
+        Decryptor::Recipients(_) =>
+            return Err(Error::InvalidIdentity(key_path.display().to_string())),
+    };
+
+    x25519::Identity::from_str(contents.trim())
+        .map_err(|_| Error::InvalidIdentity(key_path.display().to_string()))
+}
+
+/// Reads the passphrase from the environment, or prompts for it.
+fn passphrase(prompt: &str) -> Result<SecretString, Error> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_VAR) {
+        return Ok(SecretString::new(passphrase));
+    }
+
+    let mut stderr = io::stderr();
+    write!(stderr, "{}", prompt).map_err(Error::IO)?;
+    stderr.flush().map_err(Error::IO)?;
+    let passphrase = io::stdin().read_passwd(&mut stderr).map_err(Error::IO)?;
+    writeln!(stderr).map_err(Error::IO)?;
+    match passphrase {
+        Some(passphrase) if !passphrase.is_empty() => Ok(SecretString::new(passphrase)),
+        _ => Err(Error::NoPassphrase),
+    }
+}
+
+/// Encrypts the contents of a note.
+pub(crate) fn encrypt(opt: &Opt, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+    let recipient = unlock(opt)?.to_public();
+    let encryptor = Encryptor::with_recipients(vec![Box::new(recipient)])
+        .expect("there is always one recipient");
+
+    let mut encrypted = vec![];
+    let mut writer = encryptor.wrap_output(&mut encrypted).map_err(Error::Encrypt)?;
+    writer.write_all(plaintext).map_err(Error::IO)?;
+    writer.finish().map_err(Error::IO)?;
+    Ok(encrypted)
+}
+
+/// Decrypts the contents of a note in memory.
+pub(crate) fn decrypt(opt: &Opt, path: &Path, encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+    let decrypt_error = |err| Error::Decrypt {
+        path: path.display().to_string(),
+        err,
+    };
+    let identity: &dyn age::Identity = unlock(opt)?;
+
+    let mut plaintext = vec![];
+    match Decryptor::new(encrypted).map_err(decrypt_error)? {
+        Decryptor::Recipients(decryptor) => decryptor
+            .decrypt(iter::once(identity))
+            .map_err(decrypt_error)?
+            .read_to_end(&mut plaintext)
+            .map_err(Error::IO)?,
+        Decryptor::Passphrase(_) =>
+            return Err(decrypt_error(age::DecryptError::NoMatchingKeys)),
+    };
+    Ok(plaintext)
+}
+
+/// Writes a file which only the current user can read.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(Error::IO)?;
+    file.write_all(contents).map_err(Error::IO)
+}
+
+/// Overwrites a plaintext file before removing it, so that its contents are
+/// not left behind on disk.
+pub(crate) fn shred(path: &Path) -> io::Result<()> {
+    let len = match fs::metadata(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+        Ok(metadata) => metadata.len(),
+    };
+    {
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        #[allow(clippy::cast_possible_truncation)]
+        file.write_all(&vec![0; len as usize])?;
+        file.sync_all()?;
+    }
+    fs::remove_file(path)
+}
+
+/// A private directory holding decrypted copies of notes while they are open
+/// in an editor. Every copy is shredded once the notes are sealed, or when
+/// the scratch directory is dropped.
+pub(crate) struct Scratch {
+    dir:   tempfile::TempDir,
+    /// Pairs of the encrypted note and its decrypted copy
+    notes: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Scratch {
+    /// Decrypts each of the notes into a new scratch directory. The directory
+    /// is placed in `$XDG_RUNTIME_DIR` when it exists, since that is usually
+    /// memory backed.
+    pub(crate) fn open(opt: &Opt, notes: &[(String, PathBuf)]) -> io::Result<Self> {
+        let parent = env::var_os("XDG_RUNTIME_DIR").map_or_else(env::temp_dir, PathBuf::from);
+        let dir = tempfile::Builder::new().prefix("taskn-").tempdir_in(parent)?;
+
+        let mut scratch = Self { dir, notes: vec![] };
+        for (uuid, note) in notes {
+            let copy = scratch
+                .dir
+                .path()
+                .join(uuid)
+                .with_extension(&opt.file_format);
+            let plaintext = crate::notes::read(opt, note)?;
+            write_private(&copy, plaintext.as_bytes())?;
+            scratch.notes.push((note.clone(), copy));
+        }
+        Ok(scratch)
+    }
+
+    /// The paths of the decrypted copies, to be opened in an editor.
+    pub(crate) fn paths(&self) -> Vec<PathBuf> {
+        self.notes.iter().map(|(_, copy)| copy.clone()).collect()
+    }
+
+    /// Encrypts each of the decrypted copies back into its note. Empty copies
+    /// of notes which did not exist are not saved.
+    pub(crate) fn seal(self, opt: &Opt) -> io::Result<()> {
+        for (note, copy) in &self.notes {
+            let plaintext = fs::read_to_string(copy)?;
+            if note.exists() || plaintext.chars().any(|c| !c.is_whitespace()) {
+                crate::notes::write(opt, note, &plaintext)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        for (_, copy) in &self.notes {
+            if let Err(e) = shred(copy) {
+                crate::taskn_error!("failed to remove decrypted note '{}': {}", copy.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::LazyLock;
+
+    use super::*;
+
+    /// The identity of every test, since an identity is only unlocked once
+    static TEST_IDENTITY: LazyLock<x25519::Identity> = LazyLock::new(x25519::Identity::generate);
+
+    /// Options which encrypt notes in a (temporary) root directory, with the
+    /// test identity written beside them.
+    pub(crate) fn encrypted_opt(root_dir: &Path) -> Opt {
+        let identity = root_dir.join(".identity");
+        fs::write(&identity, TEST_IDENTITY.to_string().expose_secret()).unwrap();
+        let mut opt = Opt::for_tests(root_dir);
+        opt.encrypt = true;
+        opt.identity = Some(identity.to_string_lossy().to_string());
+        opt
+    }
+
+    #[test]
+    fn test_encrypted_path() {
+        let path = encrypted_path(Path::new("notes/task.md"));
+        assert_eq!(path, Path::new("notes/task.md.age"));
+        assert!(is_encrypted(&path));
+        assert!(!is_encrypted(Path::new("notes/task.md")));
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let root = tempfile::tempdir().unwrap();
+        let opt = encrypted_opt(root.path());
+        let encrypted = encrypt(&opt, b"a secret note").unwrap();
+        assert!(!encrypted
+            .windows(b"secret".len())
+            .any(|window| window == b"secret"));
+        let path = Path::new("task.md.age");
+        assert_eq!(decrypt(&opt, path, &encrypted).unwrap(), b"a secret note");
+        assert!(decrypt(&opt, path, b"not encrypted").is_err());
+    }
+
+    #[test]
+    fn test_shred() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("task.md");
+        fs::write(&path, "plaintext").unwrap();
+        shred(&path).unwrap();
+        assert!(!path.exists());
+        // a missing file has nothing left to shred
+        shred(&path).unwrap();
+    }
+}
@@ -0,0 +1,368 @@
+//! Keeps the history of the notes directory in a git repository.
+//!
+//! When enabled with `--git`, the root directory is initialized as a git
+//! repository and every change taskn makes to the notes is committed.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use thiserror::Error;
+
+use crate::{crypt, opt::Opt, taskwarrior::Task};
+
+/// The name of the remote which notes are pushed to and pulled from
+const REMOTE: &str = "origin";
+
+/// Errors used within this file
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    /// The root directory has not been set up as a repository
+    #[error("'{0}' is not a git repository, run taskn with --git to create it")]
+    NotARepository(String),
+    /// A path which is outside of the root directory
+    #[error("'{0}' is not inside of the notes directory")]
+    OutsideRoot(String),
+    /// No remote has been configured to sync with
+    #[error("no remote configured, pass one with --git-remote")]
+    NoRemote,
+    /// Error running a git subcommand
+    #[error("`git {command}` failed: {stderr}")]
+    GitCmd { command: String, stderr: String },
+    /// General IO error
+    #[error("IO error running git: {0}")]
+    IO(#[source] io::Error),
+}
+
+/// Builds a git command which runs inside of the root directory.
+fn git(opt: &Opt) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(&opt.root_dir);
+    command
+}
+
+/// Runs a git subcommand, turning an unsuccessful exit into an [Error].
+fn run(opt: &Opt, args: &[&str]) -> Result<Output, Error> {
+    let output = git(opt).args(args).output().map_err(Error::IO)?;
+    if output.status.success() {
+        Ok(output)
+    } else {
+        Err(Error::GitCmd {
+            command: args.join(" "),
+            stderr:  String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        })
+    }
+}
+
+/// Determines whether the root directory is a git repository.
+pub(crate) fn is_repository(opt: &Opt) -> bool {
+    Path::new(&opt.root_dir).join(".git").exists()
+}
+
+fn ensure_repository(opt: &Opt) -> Result<(), Error> {
+    if is_repository(opt) {
+        Ok(())
+    } else {
+        Err(Error::NotARepository(opt.root_dir.clone()))
+    }
+}
+
+/// Initializes the root directory as a git repository if it is not one
+/// already, and points the remote at `--git-remote` when one is given.
+pub(crate) fn init(opt: &Opt) -> Result<(), Error> {
+    fs::create_dir_all(&opt.root_dir).map_err(Error::IO)?;
+    if !is_repository(opt) {
+        run(opt, &["init", "--quiet"])?;
+    }
+    ignore_key(opt)?;
+
+    if let Some(remote) = &opt.git_remote {
+        let has_remote = git(opt)
+            .args(["remote", "get-url", REMOTE])
+            .output()
+            .map_err(Error::IO)?
+            .status
+            .success();
+        if has_remote {
+            run(opt, &["remote", "set-url", REMOTE, remote])?;
+        } else {
+            run(opt, &["remote", "add", REMOTE, remote])?;
+        }
+    }
+    Ok(())
+}
+
+/// Keeps the passphrase protected identity of encrypted notes out of the
+/// repository, so that it isn't pushed to the remote along with the notes.
+fn ignore_key(opt: &Opt) -> Result<(), Error> {
+    let path = Path::new(&opt.root_dir).join(".gitignore");
+    let mut ignored = match fs::read_to_string(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(Error::IO(e)),
+        Ok(ignored) => ignored,
+    };
+    let entry = format!("/{}", crypt::KEY_NAME);
+    if ignored.lines().any(|line| line.trim() == entry) {
+        return Ok(());
+    }
+    if !ignored.is_empty() && !ignored.ends_with('\n') {
+        ignored.push('\n');
+    }
+    ignored.push_str(&entry);
+    ignored.push('\n');
+    fs::write(&path, ignored).map_err(Error::IO)?;
+
+    // a key committed before it was ignored stops being tracked
+    run(opt, &["rm", "--cached", "--quiet", "--ignore-unmatch", crypt::KEY_NAME])?;
+    Ok(())
+}
+
+/// Commits every change inside of the root directory. Returns whether there
+/// was anything to commit.
+pub(crate) fn commit(opt: &Opt, message: &str) -> Result<bool, Error> {
+    run(opt, &["add", "--all"])?;
+    let unchanged = git(opt)
+        .args(["diff", "--cached", "--quiet"])
+        .status()
+        .map_err(Error::IO)?
+        .success();
+    if unchanged {
+        return Ok(false);
+    }
+
+    // fall back to a taskn identity on machines where git isn't configured, so
+    // that history is still recorded
+    let mut args = vec![];
+    if run(opt, &["config", "user.email"]).is_err() {
+        args.extend(["-c", "user.name=taskn", "-c", "user.email=taskn@localhost"]);
+    }
+    args.extend(["commit", "--quiet", "--message", message]);
+    run(opt, &args)?;
+    Ok(true)
+}
+
+/// Commits the notes of the given tasks when `--git` is enabled, naming each
+/// task in the commit message.
+pub(crate) fn record(opt: &Opt, action: &str, tasks: &[Task]) -> Result<(), Error> {
+    if !opt.git {
+        return Ok(());
+    }
+    init(opt)?;
+
+    let message = match tasks {
+        [task] => format!("{} {}: {}", action, task.id, task.description),
+        _ => {
+            let summary = tasks
+                .iter()
+                .map(|task| format!("- {}: {}", task.id, task.description))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{} {} notes\n\n{}", action, tasks.len(), summary)
+        },
+    };
+    commit(opt, &message)?;
+    Ok(())
+}
+
+/// The path of a note relative to the root directory, as git sees it.
+fn relative<'a>(opt: &Opt, path: &'a Path) -> Result<&'a Path, Error> {
+    path.strip_prefix(&opt.root_dir)
+        .map_err(|_| Error::OutsideRoot(path.display().to_string()))
+}
+
+/// Shows the history of a note, following it across renames.
+pub(crate) fn log(opt: &Opt, path: &Path) -> Result<(), Error> {
+    ensure_repository(opt)?;
+    let relative = relative(opt, path)?;
+    let status = git(opt)
+        .args(["log", "--follow", "--patch", "--"])
+        .arg(relative)
+        .status()
+        .map_err(Error::IO)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::GitCmd {
+            command: format!("log {}", relative.display()),
+            stderr:  String::new(),
+        })
+    }
+}
+
+/// Reads the contents of a note at a past revision. Notes are renamed, e.g.
+/// by readable naming or `taskn migrate`, so the path the note had at that
+/// revision is returned along with them.
+pub(crate) fn show(opt: &Opt, path: &Path, rev: &str) -> Result<(PathBuf, Vec<u8>), Error> {
+    ensure_repository(opt)?;
+    let relative = path_at(opt, relative(opt, path)?, rev)?;
+    let object = format!("{}:{}", rev, relative.display());
+    let contents = run(opt, &["show", &object])?.stdout;
+    Ok((Path::new(&opt.root_dir).join(relative), contents))
+}
+
+/// Finds the path a note had at a past revision, by following its history
+/// back to the latest commit touching it which is part of that revision.
+/// Encrypting a note replaces all of its contents, which git can't follow as
+/// a rename, so the note's history under its encrypted (or decrypted) name is
+/// followed too. Falls back to the current path, so that `git show` explains
+/// what's wrong.
+fn path_at(opt: &Opt, relative: &Path, rev: &str) -> Result<PathBuf, Error> {
+    let counterpart = if crypt::is_encrypted(relative) {
+        relative.with_extension("")
+    } else {
+        crypt::encrypted_path(relative)
+    };
+    for name in [relative, &counterpart] {
+        if let Some(path) = follow(opt, name, rev)? {
+            return Ok(path);
+        }
+    }
+    Ok(relative.to_path_buf())
+}
+
+/// Follows the history of a path across renames, returning the name it had
+/// in the latest commit which is part of `rev`.
+fn follow(opt: &Opt, relative: &Path, rev: &str) -> Result<Option<PathBuf>, Error> {
+    let relative_name = relative.to_string_lossy();
+    let args = [
+        "-c",
+        "core.quotePath=false",
+        "log",
+        "--follow",
+        "--name-only",
+        "--format=commit %H",
+        "--",
+        relative_name.as_ref(),
+    ];
+    let log = run(opt, &args)?.stdout;
+
+    // each commit is followed by the name the path had in it
+    let mut commit = None;
+    for line in String::from_utf8_lossy(&log).lines().filter(|line| !line.is_empty()) {
+        if let Some(hash) = line.strip_prefix("commit ") {
+            commit = Some(hash.to_string());
+        } else if let Some(hash) = commit.take() {
+            let is_ancestor = git(opt)
+                .args(["merge-base", "--is-ancestor", &hash, rev])
+                .status()
+                .map_err(Error::IO)?
+                .success();
+            if is_ancestor {
+                return Ok(Some(PathBuf::from(line)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Pulls notes from the remote, and then pushes local history to it.
+pub(crate) fn sync(opt: &Opt) -> Result<(), Error> {
+    init(opt)?;
+    if run(opt, &["remote", "get-url", REMOTE]).is_err() {
+        return Err(Error::NoRemote);
+    }
+
+    commit(opt, "Sync notes")?;
+
+    // a freshly created remote has nothing to pull yet
+    let remote_heads = run(opt, &["ls-remote", "--heads", REMOTE])?.stdout;
+    if !remote_heads.is_empty() {
+        let branch = run(opt, &["symbolic-ref", "--short", "HEAD"])?.stdout;
+        let branch = String::from_utf8_lossy(&branch).trim().to_string();
+        run(opt, &["pull", "--quiet", "--rebase", REMOTE, &branch])?;
+    }
+    run(opt, &["push", "--quiet", "--set-upstream", REMOTE, "HEAD"])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A long enough note for git to see a small change to it as a rename.
+    const NOTE: &str = "A note which is long enough for git to follow it when it's renamed.\n";
+
+    fn repository() -> (tempfile::TempDir, Opt) {
+        let root = tempfile::tempdir().unwrap();
+        let opt = Opt::for_tests(root.path());
+        init(&opt).unwrap();
+        (root, opt)
+    }
+
+    fn tracked(opt: &Opt) -> Vec<String> {
+        let files = run(opt, &["ls-files"]).unwrap().stdout;
+        String::from_utf8_lossy(&files).lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_init_ignores_the_key() {
+        let (root, opt) = repository();
+        init(&opt).unwrap();
+        let ignored = fs::read_to_string(root.path().join(".gitignore")).unwrap();
+        assert_eq!(ignored, "/.taskn-key.age\n");
+
+        fs::write(root.path().join(crypt::KEY_NAME), "key").unwrap();
+        fs::write(root.path().join("note.md"), NOTE).unwrap();
+        assert!(commit(&opt, "Add a note").unwrap());
+        assert_eq!(tracked(&opt), vec![".gitignore", "note.md"]);
+        assert!(!commit(&opt, "Nothing").unwrap());
+    }
+
+    #[test]
+    fn test_init_stops_tracking_a_committed_key() {
+        let root = tempfile::tempdir().unwrap();
+        let opt = Opt::for_tests(root.path());
+        run(&opt, &["init", "--quiet"]).unwrap();
+        fs::write(root.path().join(".gitignore"), "*.swp").unwrap();
+        fs::write(root.path().join(crypt::KEY_NAME), "key").unwrap();
+        commit(&opt, "Add the key").unwrap();
+        assert!(tracked(&opt).contains(&crypt::KEY_NAME.to_string()));
+
+        init(&opt).unwrap();
+        commit(&opt, "Stop tracking the key").unwrap();
+        assert_eq!(tracked(&opt), vec![".gitignore"]);
+        assert!(root.path().join(crypt::KEY_NAME).exists());
+        let ignored = fs::read_to_string(root.path().join(".gitignore")).unwrap();
+        assert_eq!(ignored, "*.swp\n/.taskn-key.age\n");
+    }
+
+    #[test]
+    fn test_show_follows_renames() {
+        let (root, opt) = repository();
+        let old = root.path().join("1a2b3c4d-1111-2222-3333-444455556666.md");
+        fs::write(&old, NOTE).unwrap();
+        commit(&opt, "Add a note").unwrap();
+        fs::remove_file(&old).unwrap();
+        let new = root.path().join("write-the-report--1a2b3c4d.md");
+        fs::write(&new, format!("{}And a bit more.\n", NOTE)).unwrap();
+        commit(&opt, "Rename the note").unwrap();
+
+        let (path, contents) = show(&opt, &new, "HEAD~1").unwrap();
+        assert_eq!(path, old);
+        assert_eq!(contents, NOTE.as_bytes());
+        assert_eq!(show(&opt, &new, "HEAD").unwrap().0, new);
+    }
+
+    #[test]
+    fn test_show_follows_encryption() {
+        let (root, opt) = repository();
+        let plaintext = root.path().join("note.md");
+        fs::write(&plaintext, NOTE).unwrap();
+        commit(&opt, "Add a note").unwrap();
+        fs::remove_file(&plaintext).unwrap();
+        let encrypted = crypt::encrypted_path(&plaintext);
+        fs::write(&encrypted, "encrypted").unwrap();
+        commit(&opt, "Encrypt the note").unwrap();
+
+        assert_eq!(show(&opt, &encrypted, "HEAD~1").unwrap(), (
+            plaintext,
+            NOTE.as_bytes().to_vec()
+        ));
+        // there's nothing to show from before the note was written
+        fs::write(root.path().join("other.md"), "other").unwrap();
+        commit(&opt, "Add another note").unwrap();
+        assert!(show(&opt, &root.path().join("other.md"), "HEAD~1").is_err());
+    }
+}
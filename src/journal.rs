@@ -0,0 +1,393 @@
+//! A journal of the changes taskn makes to tasks.
+//!
+//! Every change made from the interactive view is recorded as an
+//! [Operation], holding each task's attributes before and after, so that it
+//! can be undone within the session or later with `taskn undo`. The journal is
+//! kept at `$XDG_STATE_HOME/taskn/journal`, one operation per line.
+
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::taskwarrior::{ParsableDateTime, Task};
+
+/// Errors used within this file
+#[derive(Debug, Error)]
+pub(crate) enum Error {
+    /// Error reading or writing the journal
+    #[error("failed to access journal '{path}': {err}")]
+    IO {
+        path: String,
+        #[source]
+        err:  io::Error,
+    },
+    /// A line of the journal which can't be read back
+    #[error("invalid journal entry in '{path}': {err}")]
+    Parse {
+        path: String,
+        #[source]
+        err:  serde_json::Error,
+    },
+    /// Error running `task modify` to put a task back
+    #[error("`task {uuid} modify` failed: {stderr}")]
+    TaskCmd { uuid: String, stderr: String },
+}
+
+/// The attributes of a task which taskn changes, as taskwarrior reads them
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    pub(crate) description: String,
+    pub(crate) status:      String,
+    pub(crate) project:     Option<String>,
+    pub(crate) tags:        Vec<String>,
+    pub(crate) priority:    Option<String>,
+    pub(crate) due:         Option<String>,
+    pub(crate) wait:        Option<String>,
+    pub(crate) scheduled:   Option<String>,
+    /// The position of the task in the manual order
+    pub(crate) estimate:    Option<String>,
+}
+
+impl Snapshot {
+    pub(crate) fn of(task: &Task) -> Self {
+        let date = |date: &Option<ParsableDateTime>| {
+            date.as_ref().map(ParsableDateTime::to_taskwarrior)
+        };
+        Self {
+            description: task.description.clone(),
+            status:      task.status.clone(),
+            project:     task.project.clone(),
+            tags:        task.tags.clone().unwrap_or_default(),
+            priority:    task.priority.clone(),
+            due:         date(&task.due),
+            wait:        date(&task.wait),
+            scheduled:   date(&task.scheduled),
+            estimate:    task.estimate.clone(),
+        }
+    }
+
+    /// The name of each attribute along with its value, for showing it.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, String)> {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            ("description", self.description.clone()),
+            ("status", self.status.clone()),
+            ("project", optional(&self.project)),
+            ("tags", self.tags.join(" ")),
+            ("priority", optional(&self.priority)),
+            ("due", optional(&self.due)),
+            ("wait", optional(&self.wait)),
+            ("scheduled", optional(&self.scheduled)),
+            ("order", optional(&self.estimate)),
+        ]
+    }
+
+    /// The names of the attributes which differ from another snapshot.
+    fn differences(&self, other: &Self) -> Vec<&'static str> {
+        self.fields()
+            .into_iter()
+            .zip(other.fields())
+            .filter(|((_, value), (_, other))| value != other)
+            .map(|((name, _), _)| name)
+            .collect()
+    }
+
+    /// Writes the attributes onto a task in taskwarrior.
+    fn write(&self, uuid: &str) -> Result<(), Error> {
+        let optional = |name: &str, value: &Option<String>| {
+            format!("{}:{}", name, value.as_deref().unwrap_or_default())
+        };
+        let output = Command::new("task")
+            .arg("rc.bulk=0")
+            .arg("rc.confirmation=off")
+            .arg("rc.dependency.confirmation=off")
+            .arg("rc.recurrence.confirmation=off")
+            .arg(uuid)
+            .arg("modify")
+            .arg(&self.description)
+            .arg(format!("status:{}", self.status))
+            .arg(optional("project", &self.project))
+            .arg(format!("tags:{}", self.tags.join(",")))
+            .arg(optional("priority", &self.priority))
+            .arg(optional("due", &self.due))
+            .arg(optional("wait", &self.wait))
+            .arg(optional("scheduled", &self.scheduled))
+            .arg(optional("estimate", &self.estimate))
+            .output()
+            .map_err(|err| Error::TaskCmd {
+                uuid:   uuid.to_string(),
+                stderr: err.to_string(),
+            })?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::TaskCmd {
+                uuid:   uuid.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            })
+        }
+    }
+}
+
+/// A task before and after it was changed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Change {
+    pub(crate) uuid:   String,
+    pub(crate) id:     usize,
+    pub(crate) before: Snapshot,
+    pub(crate) after:  Snapshot,
+}
+
+impl Change {
+    /// Compares a task as it was loaded with how it is now. Returns nothing
+    /// when it wasn't changed.
+    pub(crate) fn new(before: &Task, after: &Task) -> Option<Self> {
+        let change = Self {
+            uuid:   after.uuid.clone(),
+            id:     after.id,
+            before: Snapshot::of(before),
+            after:  Snapshot::of(after),
+        };
+        (change.before != change.after).then_some(change)
+    }
+}
+
+/// Changes to one or more tasks made by a single action
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Operation {
+    /// What was changed, e.g. `status of 3 tasks`
+    pub(crate) summary: String,
+    /// When the change was made, in RFC 3339
+    pub(crate) time:    String,
+    pub(crate) changes: Vec<Change>,
+}
+
+impl Operation {
+    /// Groups changes into an operation. Returns nothing when there are no
+    /// changes.
+    pub(crate) fn new(changes: Vec<Change>) -> Option<Self> {
+        let mut fields = changes
+            .iter()
+            .flat_map(|change| change.before.differences(&change.after))
+            .collect::<Vec<_>>();
+        fields.sort_unstable();
+        fields.dedup();
+        let which = match changes.as_slice() {
+            [] => return None,
+            [change] => format!("task {}", change.id),
+            changes => format!("{} tasks", changes.len()),
+        };
+        Some(Self {
+            summary: format!("{} of {}", fields.join(", "), which),
+            time: Local::now().to_rfc3339(),
+            changes,
+        })
+    }
+
+    /// Puts every task back the way it was before the operation.
+    pub(crate) fn undo(&self) -> Result<(), Error> {
+        for (uuid, snapshot) in self.snapshots(true) {
+            snapshot.write(uuid)?;
+        }
+        Ok(())
+    }
+
+    /// Makes the operation's changes again, after it was undone.
+    pub(crate) fn redo(&self) -> Result<(), Error> {
+        for (uuid, snapshot) in self.snapshots(false) {
+            snapshot.write(uuid)?;
+        }
+        Ok(())
+    }
+
+    /// What each task is written back as: how it was before the operation
+    /// when undoing it, and how it was after when redoing it.
+    fn snapshots(&self, undo: bool) -> impl Iterator<Item = (&str, &Snapshot)> + '_ {
+        self.changes.iter().map(move |change| {
+            let snapshot = if undo { &change.before } else { &change.after };
+            (change.uuid.as_str(), snapshot)
+        })
+    }
+}
+
+/// The operations made so far, oldest first, stored across sessions
+pub(crate) struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub(crate) fn open() -> Self {
+        Self { path: default_path() }
+    }
+
+    fn io_error(&self, err: io::Error) -> Error {
+        Error::IO {
+            path: self.path.display().to_string(),
+            err,
+        }
+    }
+
+    fn read(&self) -> Result<Vec<String>, Error> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(contents.lines().map(String::from).collect()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(err) => Err(self.io_error(err)),
+        }
+    }
+
+    /// Records an operation as the latest one.
+    pub(crate) fn push(&self, operation: &Operation) -> Result<(), Error> {
+        let line = serde_json::to_string(operation).map_err(|err| Error::Parse {
+            path: self.path.display().to_string(),
+            err,
+        })?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|err| self.io_error(err))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| self.io_error(err))?;
+        writeln!(file, "{}", line).map_err(|err| self.io_error(err))
+    }
+
+    /// Removes the latest operation, returning it.
+    pub(crate) fn pop(&self) -> Result<Option<Operation>, Error> {
+        let mut lines = self.read()?;
+        let last = match lines.pop() {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+        let operation = serde_json::from_str(&last).map_err(|err| Error::Parse {
+            path: self.path.display().to_string(),
+            err,
+        })?;
+        let mut contents = lines.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        fs::write(&self.path, contents).map_err(|err| self.io_error(err))?;
+        Ok(Some(operation))
+    }
+
+    /// Removes the latest operation if it's the given one, e.g. once it was
+    /// undone within a session.
+    pub(crate) fn forget(&self, operation: &Operation) -> Result<(), Error> {
+        let is_latest = self.read()?.last().is_some_and(|last| {
+            serde_json::from_str::<Operation>(last).is_ok_and(|last| last.time == operation.time)
+        });
+        if is_latest {
+            self.pop()?;
+        }
+        Ok(())
+    }
+}
+
+fn default_path() -> PathBuf {
+    let dir = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state"))
+        })
+        .unwrap_or_default();
+    dir.join("taskn").join("journal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(uuid: &str, status: &str, project: Option<&str>) -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "uuid": uuid,
+            "description": "Write the report",
+            "status": status,
+            "project": project,
+            "tags": ["work"],
+            "estimate": "3",
+        }))
+        .unwrap()
+    }
+
+    fn operation(before: &Task, after: &Task) -> Operation {
+        Operation::new(Change::new(before, after).into_iter().collect()).unwrap()
+    }
+
+    #[test]
+    fn test_change_new() {
+        let before = task("a", "pending", None);
+        assert!(Change::new(&before, &before.clone()).is_none());
+
+        let after = task("a", "completed", Some("home"));
+        let change = Change::new(&before, &after).unwrap();
+        assert_eq!(change.before, Snapshot::of(&before));
+        assert_eq!(change.after, Snapshot::of(&after));
+        assert_eq!(change.before.differences(&change.after), vec!["status", "project"]);
+    }
+
+    #[test]
+    fn test_operation_summary() {
+        assert!(Operation::new(vec![]).is_none());
+
+        let before = task("a", "pending", None);
+        let done = task("a", "completed", None);
+        assert_eq!(operation(&before, &done).summary, "status of task 1");
+
+        let moved = (task("b", "pending", None), task("b", "pending", Some("home")));
+        let changes = vec![
+            Change::new(&before, &done).unwrap(),
+            Change::new(&moved.0, &moved.1).unwrap(),
+        ];
+        assert_eq!(Operation::new(changes).unwrap().summary, "project, status of 2 tasks");
+    }
+
+    #[test]
+    fn test_undo_and_redo_are_inverse() {
+        let before = task("a", "pending", None);
+        let after = task("a", "completed", Some("home"));
+        let operation = operation(&before, &after);
+
+        let undone = operation.snapshots(true).collect::<Vec<_>>();
+        assert_eq!(undone, vec![("a", &Snapshot::of(&before))]);
+        let redone = operation.snapshots(false).collect::<Vec<_>>();
+        assert_eq!(redone, vec![("a", &Snapshot::of(&after))]);
+
+        // undoing writes the same as making the opposite change
+        let inverse = Change::new(&after, &before).unwrap();
+        assert_eq!(&inverse.after, undone[0].1);
+        assert_eq!(&inverse.before, redone[0].1);
+    }
+
+    #[test]
+    fn test_journal_push_pop_forget() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal {
+            path: dir.path().join("taskn").join("journal"),
+        };
+        assert!(journal.pop().unwrap().is_none());
+
+        let before = task("a", "pending", None);
+        let first = operation(&before, &task("a", "completed", None));
+        let mut second = operation(&before, &task("a", "deleted", None));
+        second.time = format!("{} later", first.time);
+        journal.push(&first).unwrap();
+        journal.push(&second).unwrap();
+
+        // only the latest operation is forgotten
+        journal.forget(&first).unwrap();
+        assert_eq!(journal.pop().unwrap().unwrap().time, second.time);
+        journal.forget(&first).unwrap();
+        assert!(journal.pop().unwrap().is_none());
+    }
+}
@@ -0,0 +1,275 @@
+//! Wiki-style links between task notes.
+//!
+//! A note may reference another task with `[[task:<target>]]`, where the
+//! target is a task ID, a (short) UUID, or the task's description. Targets
+//! are rewritten to full UUIDs when the note is edited, so links survive
+//! taskwarrior renumbering its IDs.
+
+use std::{collections::HashMap, io};
+
+use crate::{
+    notes::{self, Resolver},
+    taskwarrior::Task,
+};
+
+const LINK_START: &str = "[[task:";
+const LINK_END: &str = "]]";
+
+/// A single `[[task:...]]` link inside of a note
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Link {
+    /// The text between `[[task:` and `]]`
+    pub(crate) target: String,
+    /// Byte offset of the start of the link in the note
+    pub(crate) start:  usize,
+    /// Byte offset one past the end of the link in the note
+    pub(crate) end:    usize,
+}
+
+/// Finds every link inside of the contents of a note.
+pub(crate) fn parse(contents: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = contents[offset..].find(LINK_START) {
+        let start = offset + start;
+        let target_start = start + LINK_START.len();
+        let end = match contents[target_start..].find(LINK_END) {
+            Some(end) => target_start + end,
+            None => break,
+        };
+        let target = contents[target_start..end].trim();
+        // a link never spans multiple lines
+        if !target.is_empty() && !target.contains('\n') {
+            links.push(Link {
+                target: target.to_string(),
+                start,
+                end: end + LINK_END.len(),
+            });
+        }
+        offset = end + LINK_END.len();
+    }
+    links
+}
+
+/// Determines whether or not a string looks like a full taskwarrior UUID.
+pub(crate) fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Finds the task that a link target refers to. Targets are tried as a UUID,
+/// a short UUID, a task ID and finally as a (case insensitive) description.
+/// Short UUIDs come before IDs, since they can be all digits. Returns `None`
+/// if no task, or more than one task, matches.
+pub(crate) fn resolve<'a>(target: &str, tasks: &'a [Task]) -> Option<&'a Task> {
+    if is_uuid(target) {
+        return tasks.iter().find(|task| task.uuid == target);
+    }
+
+    let live = || tasks.iter().filter(|task| task.status != "deleted");
+    let unique = |mut matches: Vec<&'a Task>| {
+        if matches.len() == 1 {
+            matches.pop()
+        } else {
+            None
+        }
+    };
+
+    if target.len() >= 8 && target.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        let matches = live()
+            .filter(|task| task.uuid.starts_with(target))
+            .collect::<Vec<_>>();
+        if !matches.is_empty() {
+            return unique(matches);
+        }
+    }
+
+    if let Ok(id) = target.parse::<usize>() {
+        if id != 0 {
+            return tasks.iter().find(|task| task.id == id);
+        }
+    }
+
+    let target = target.to_lowercase();
+    unique(
+        live()
+            .filter(|task| task.description.to_lowercase() == target)
+            .collect(),
+    )
+}
+
+/// Rewrites every link in `contents` so that it points to a task's UUID.
+/// Links that cannot be resolved are left as they are, and are returned
+/// alongside the new contents.
+pub(crate) fn rewrite(contents: &str, tasks: &[Task]) -> (String, Vec<String>) {
+    let mut rewritten = String::with_capacity(contents.len());
+    let mut unresolved = Vec::new();
+    let mut offset = 0;
+    for link in parse(contents) {
+        rewritten.push_str(&contents[offset..link.start]);
+        match resolve(&link.target, tasks) {
+            Some(task) => {
+                rewritten.push_str(LINK_START);
+                rewritten.push_str(&task.uuid);
+                rewritten.push_str(LINK_END);
+            },
+            None => {
+                rewritten.push_str(&contents[link.start..link.end]);
+                unresolved.push(link.target);
+            },
+        }
+        offset = link.end;
+    }
+    rewritten.push_str(&contents[offset..]);
+    (rewritten, unresolved)
+}
+
+/// Returns the targets of every link in `contents` which point to a task that
+/// has been deleted or that no longer exists.
+pub(crate) fn broken(contents: &str, tasks: &[Task]) -> Vec<String> {
+    parse(contents)
+        .into_iter()
+        .filter(|link| {
+            is_uuid(&link.target)
+                && !tasks
+                    .iter()
+                    .any(|task| task.uuid == link.target && task.status != "deleted")
+        })
+        .map(|link| link.target)
+        .collect()
+}
+
+/// Builds a map from the UUID of a task to the keys of the notes which link to
+/// it. See [notes::key_matches] for finding the task a note belongs to.
+pub(crate) fn backlinks(resolver: &Resolver) -> io::Result<HashMap<String, Vec<String>>> {
+    let mut backlinks: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, path) in resolver.notes() {
+        let contents = notes::read(resolver.opt(), path)?;
+        for link in parse(&contents) {
+            let sources = backlinks.entry(link.target).or_default();
+            if !sources.iter().any(|source| source == key) {
+                sources.push(key.to_string());
+            }
+        }
+    }
+    Ok(backlinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "1a2b3c4d-1111-2222-3333-444455556666";
+    const SECOND: &str = "1a2b9999-1111-2222-3333-444455556666";
+    const DELETED: &str = "deadbeef-1111-2222-3333-444455556666";
+
+    fn task(id: usize, uuid: &str, description: &str, status: &str) -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "uuid": uuid,
+            "description": description,
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            task(1, FIRST, "Write the report", "pending"),
+            task(2, SECOND, "Review", "pending"),
+            task(0, DELETED, "Review", "deleted"),
+        ]
+    }
+
+    #[test]
+    fn test_parse() {
+        let contents = "see [[task: 1 ]] and [[task:review]]";
+        assert_eq!(parse(contents), vec![
+            Link {
+                target: "1".to_string(),
+                start:  4,
+                end:    16,
+            },
+            Link {
+                target: "review".to_string(),
+                start:  21,
+                end:    36,
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_skips_broken_links() {
+        assert!(parse("[[task:]] [[task:two\nlines]] [[task:unclosed").is_empty());
+        assert_eq!(parse("[[task:a\nb]] [[task:2]]").len(), 1);
+    }
+
+    #[test]
+    fn test_is_uuid() {
+        assert!(is_uuid(FIRST));
+        assert!(!is_uuid("1a2b3c4d"));
+        assert!(!is_uuid("1a2b3c4d_1111_2222_3333_444455556666"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let tasks = tasks();
+        let uuid = |target| resolve(target, &tasks).map(|task| task.uuid.as_str());
+        assert_eq!(uuid(FIRST), Some(FIRST));
+        assert_eq!(uuid("2"), Some(SECOND));
+        assert_eq!(uuid("1a2b3c4d"), Some(FIRST));
+        assert_eq!(uuid("write the REPORT"), Some(FIRST));
+        // the deleted task doesn't make the description ambiguous
+        assert_eq!(uuid("review"), Some(SECOND));
+        assert_eq!(uuid("deadbeef"), None);
+        assert_eq!(uuid("0"), None);
+        assert_eq!(uuid("missing"), None);
+    }
+
+    #[test]
+    fn test_resolve_ambiguous() {
+        let mut tasks = tasks();
+        tasks.push(task(3, "1a2b0000-1111-2222-3333-444455556666", "Review", "pending"));
+        assert!(resolve("review", &tasks).is_none());
+        assert!(resolve("1a2b", &tasks).is_none());
+        assert!(resolve("1a2b3c4d", &tasks).is_some());
+    }
+
+    #[test]
+    fn test_resolve_digit_short_uuid() {
+        let mut tasks = tasks();
+        tasks.push(task(3, "12345678-1111-2222-3333-444455556666", "Plan", "pending"));
+        tasks.push(task(12_345_679, "abcdef00-1111-2222-3333-444455556666", "Ship", "pending"));
+        let uuid = |target| resolve(target, &tasks).map(|task| task.uuid.as_str());
+        assert_eq!(uuid("12345678"), Some("12345678-1111-2222-3333-444455556666"));
+        // without a matching short UUID, the target is still an ID
+        assert_eq!(uuid("12345679"), Some("abcdef00-1111-2222-3333-444455556666"));
+        assert_eq!(uuid("3"), Some("12345678-1111-2222-3333-444455556666"));
+    }
+
+    #[test]
+    fn test_rewrite() {
+        let (rewritten, unresolved) =
+            rewrite("[[task:1]], [[task:missing]] and [[task:review]].", &tasks());
+        assert_eq!(
+            rewritten,
+            format!("[[task:{}]], [[task:missing]] and [[task:{}]].", FIRST, SECOND)
+        );
+        assert_eq!(unresolved, vec!["missing".to_string()]);
+    }
+
+    #[test]
+    fn test_broken() {
+        let contents = format!(
+            "[[task:{}]] [[task:{}]] [[task:abcdef00-1111-2222-3333-444455556666]] [[task:1]]",
+            FIRST, DELETED
+        );
+        assert_eq!(broken(&contents, &tasks()), vec![
+            DELETED.to_string(),
+            "abcdef00-1111-2222-3333-444455556666".to_string(),
+        ]);
+    }
+}
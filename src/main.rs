@@ -8,7 +8,6 @@
     absolute_paths_not_starting_with_crate,
     anonymous_parameters,
     bad_style,
-    const_err,
     dead_code,
     keyword_idents,
     improper_ctypes,
@@ -21,13 +20,10 @@
     overflowing_literals,
     path_statements,
     patterns_in_fns_without_body,
-    pointer_structural_match,
-    private_in_public,
     semicolon_in_expressions_from_macros,
     single_use_lifetimes,
     trivial_casts,
     trivial_numeric_casts,
-    unaligned_references,
     unconditional_recursion,
     unreachable_pub,
     unused,
@@ -47,13 +43,26 @@
     clippy::shadow_reuse,
     clippy::too_many_lines,
     clippy::doc_markdown,
-    clippy::single_match_else
+    clippy::single_match_else,
+    clippy::uninlined_format_args,
+    clippy::needless_lifetimes,
+    clippy::elidable_lifetime_names,
+    clippy::collapsible_match,
+    clippy::manual_let_else
 )]
 
 mod commands;
+mod config;
+mod crypt;
+mod git;
+mod journal;
+mod links;
+mod notes;
 mod opt;
 mod taskwarrior;
 
+use std::process;
+
 use opt::Opt;
 use colored::Colorize;
 
@@ -64,10 +73,19 @@ macro_rules! taskn_error {
     })
 }
 
+#[macro_export]
+macro_rules! taskn_warning {
+    ($($err:tt)*) => ({
+        eprintln!("{}: {}", "[taskn warning]".yellow().bold(), format!($($err)*));
+    })
+}
+
 fn main() {
     let opt = Opt::from_args();
 
     if let Err(e) = opt.command.execute(&opt) {
         taskn_error!("{}", e);
+        // scripts rely on the exit code to know whether a command worked
+        process::exit(1);
     }
 }
@@ -0,0 +1,842 @@
+//! Locating task notes (and their attachments) on disk.
+//!
+//! Notes are stored in one of two layouts inside of the root directory:
+//!
+//! - [Layout::Flat]: each note is a single `<name>.<ext>` file.
+//! - [Layout::Directory]: each task has a `<name>/` directory holding its note
+//!   at `<name>/note.<ext>` and any attachments next to it.
+//!
+//! The name is chosen by the [Naming] scheme: either the task's UUID, or a
+//! readable `<project>/<slug>--<short-uuid>` which follows the task's project
+//! and description. Either way, the [Resolver] finds a task's note by the UUID
+//! in its name, so notes are found no matter how they were named.
+//!
+//! With `--encrypt`, notes are stored encrypted with an additional `.age`
+//! extension, and are only ever decrypted in memory.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{crypt, links::is_uuid, opt::Opt, taskwarrior::Task};
+
+/// The name of the note file inside of a task's directory
+const NOTE_NAME: &str = "note";
+
+/// The number of characters of a UUID used in readable names
+const SHORT_UUID_LEN: usize = 8;
+
+/// The longest slug of a description used in readable names
+const MAX_SLUG_LEN: usize = 50;
+
+/// How notes are laid out inside of the root directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Layout {
+    /// `<root>/<name>.<ext>`
+    Flat,
+    /// `<root>/<name>/note.<ext>`, with attachments beside the note
+    Directory,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "flat" => Ok(Self::Flat),
+            "dir" | "directory" => Ok(Self::Directory),
+            _ => Err(format!("failed to parse layout from '{}'", s)),
+        }
+    }
+}
+
+/// How notes are named inside of the root directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Naming {
+    /// `<uuid>`
+    Uuid,
+    /// `<project>/<slug-of-description>--<short-uuid>`, where each part of a
+    /// dotted project is its own directory
+    Readable,
+}
+
+impl FromStr for Naming {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "uuid" => Ok(Self::Uuid),
+            "readable" => Ok(Self::Readable),
+            _ => Err(format!("failed to parse naming scheme from '{}'", s)),
+        }
+    }
+}
+
+/// Finds the notes of tasks on disk, and decides where new notes are placed.
+///
+/// A resolver scans the root directory once when it is created, indexing each
+/// note by the (short) UUID inside of its name.
+pub(crate) struct Resolver<'a> {
+    opt:   &'a Opt,
+    /// Maps the (short) UUID of a note's name to the note's path
+    notes: HashMap<String, PathBuf>,
+    /// Maps the (short) UUID of a task directory's name to the directory, for
+    /// directories which hold attachments but no note
+    dirs:  HashMap<String, PathBuf>,
+}
+
+impl<'a> Resolver<'a> {
+    pub(crate) fn new(opt: &'a Opt) -> io::Result<Self> {
+        let mut resolver = Self {
+            opt,
+            notes: HashMap::new(),
+            dirs: HashMap::new(),
+        };
+        resolver.scan(Path::new(&opt.root_dir))?;
+        Ok(resolver)
+    }
+
+    pub(crate) fn opt(&self) -> &'a Opt {
+        self.opt
+    }
+
+    fn scan(&mut self, dir: &Path) -> io::Result<()> {
+        let entries = match fs::read_dir(dir) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+            Ok(entries) => entries,
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            let name = match path.file_name().and_then(|name| name.to_str()) {
+                // skips the git repository and the encryption key
+                Some(name) if !name.starts_with('.') => name.to_string(),
+                _ => continue,
+            };
+
+            if path.is_dir() {
+                match key_of(&name) {
+                    Some(key) => {
+                        let note = self
+                            .note_names()
+                            .iter()
+                            .map(|note_name| path.join(note_name))
+                            .find(|note| note.exists());
+                        match note {
+                            Some(note) => self.notes.insert(key, note),
+                            None => self.dirs.insert(key, path),
+                        };
+                    },
+                    // any other directory holds the notes of a project
+                    None => self.scan(&path)?,
+                }
+            } else if let Some(key) = self.strip_extension(&name).and_then(key_of) {
+                self.notes.insert(key, path);
+            }
+        }
+        Ok(())
+    }
+
+    /// The names a note may have inside of a task's directory.
+    fn note_names(&self) -> [String; 2] {
+        let name = format!("{}.{}", NOTE_NAME, self.opt.file_format);
+        [format!("{}.{}", name, crypt::EXTENSION), name]
+    }
+
+    fn strip_extension<'n>(&self, name: &'n str) -> Option<&'n str> {
+        let suffix = format!(".{}", self.opt.file_format);
+        let encrypted_suffix = format!("{}.{}", suffix, crypt::EXTENSION);
+        name.strip_suffix(&encrypted_suffix)
+            .or_else(|| name.strip_suffix(&suffix))
+    }
+
+    /// Lists the key and path of every note, sorted by path. The key is the
+    /// UUID, or the short UUID, of the task that the note belongs to.
+    pub(crate) fn notes(&self) -> Vec<(&str, &Path)> {
+        let mut notes = self
+            .notes
+            .iter()
+            .map(|(key, path)| (key.as_str(), path.as_path()))
+            .collect::<Vec<_>>();
+        notes.sort_by_key(|(_, path)| *path);
+        notes
+    }
+
+    /// Lists the key and path of every task directory which holds
+    /// attachments but no note, sorted by path.
+    pub(crate) fn dirs(&self) -> Vec<(&str, &Path)> {
+        let mut dirs = self
+            .dirs
+            .iter()
+            .map(|(key, path)| (key.as_str(), path.as_path()))
+            .collect::<Vec<_>>();
+        dirs.sort_by_key(|(_, path)| *path);
+        dirs
+    }
+
+    fn existing(&self, task: &Task) -> Option<&PathBuf> {
+        self.notes
+            .get(&task.uuid)
+            .or_else(|| self.notes.get(short_uuid(&task.uuid)))
+    }
+
+    fn existing_dir(&self, task: &Task) -> Option<&PathBuf> {
+        self.dirs
+            .get(&task.uuid)
+            .or_else(|| self.dirs.get(short_uuid(&task.uuid)))
+    }
+
+    /// The key a task's note or directory is indexed by once it's named with
+    /// the selected naming scheme.
+    fn key(&self, task: &Task) -> String {
+        match self.opt.naming {
+            Naming::Uuid => task.uuid.clone(),
+            Naming::Readable => short_uuid(&task.uuid).to_string(),
+        }
+    }
+
+    /// The path of a task's note: where it is on disk if it exists, or where a
+    /// new note should be placed if it doesn't.
+    pub(crate) fn note_path(&self, task: &Task) -> PathBuf {
+        self.existing(task)
+            .cloned()
+            .unwrap_or_else(|| self.expected_path(task, self.opt.layout, self.opt.encrypt))
+    }
+
+    /// Where a task's note belongs with a specific layout and encryption.
+    pub(crate) fn expected_path(&self, task: &Task, layout: Layout, encrypted: bool) -> PathBuf {
+        let base = self.base(task);
+        let path = match layout {
+            Layout::Flat => base.with_extension(&self.opt.file_format),
+            Layout::Directory => base.join(NOTE_NAME).with_extension(&self.opt.file_format),
+        };
+        if encrypted {
+            crypt::encrypted_path(&path)
+        } else {
+            path
+        }
+    }
+
+    /// The path of a task's note without any extension. In
+    /// [Layout::Directory] this is the task's directory.
+    fn base(&self, task: &Task) -> PathBuf {
+        let root = PathBuf::new().join(&self.opt.root_dir);
+        match self.opt.naming {
+            Naming::Uuid => root.join(&task.uuid),
+            Naming::Readable => {
+                let mut path = root;
+                if let Some(project) = &task.project {
+                    for part in project.split('.').map(slug).filter(|part| !part.is_empty()) {
+                        path.push(part);
+                    }
+                }
+                path.join(readable_name(&task.description, &task.uuid))
+            },
+        }
+    }
+
+    /// The directory holding a task's note and attachments, if the task has
+    /// one or would be given one in the selected layout.
+    pub(crate) fn task_dir(&self, task: &Task) -> Option<PathBuf> {
+        if let Some(note) = self.existing(task) {
+            if in_directory(note) {
+                return note.parent().map(Path::to_path_buf);
+            }
+        }
+        match (self.existing_dir(task), self.opt.layout) {
+            (Some(dir), _) => Some(dir.clone()),
+            (None, Layout::Directory) => Some(self.base(task)),
+            (None, Layout::Flat) => None,
+        }
+    }
+
+    /// Creates the directory holding a task's attachments. In
+    /// [Layout::Directory], a flat note is first moved into the directory, so
+    /// that the task isn't split between two places.
+    pub(crate) fn prepare_dir(&mut self, task: &Task) -> io::Result<Option<PathBuf>> {
+        if self.opt.layout == Layout::Directory {
+            if let Some(note) = self.existing(task).filter(|note| !in_directory(note)) {
+                let encrypted = crypt::is_encrypted(note);
+                self.relocate(task, Layout::Directory, encrypted)?;
+            }
+        }
+        let dir = self.task_dir(task);
+        if let Some(dir) = &dir {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(dir)
+    }
+
+    /// Lists the attachments stored beside a task's note.
+    pub(crate) fn attachments(&self, task: &Task) -> io::Result<Vec<PathBuf>> {
+        let dir = match self.task_dir(task) {
+            Some(dir) => dir,
+            None => return Ok(Vec::new()),
+        };
+        let entries = match fs::read_dir(dir) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+            Ok(entries) => entries,
+        };
+
+        let note_names = self.note_names();
+        let mut attachments = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let is_note = path
+                .file_name()
+                .is_some_and(|name| note_names.iter().any(|note_name| name == note_name.as_str()));
+            if !is_note {
+                attachments.push(path);
+            }
+        }
+        attachments.sort();
+        Ok(attachments)
+    }
+
+    /// Reads the contents of a task's note.
+    pub(crate) fn read(&self, task: &Task) -> io::Result<String> {
+        read(self.opt, &self.note_path(task))
+    }
+
+    /// Makes sure that the directory a task's note is written to exists. With
+    /// `--encrypt`, a plaintext note is encrypted first, so that it is never
+    /// written back unencrypted.
+    pub(crate) fn prepare(&mut self, task: &Task) -> io::Result<()> {
+        if self.opt.encrypt {
+            self.seal(task)?;
+        }
+        match self.note_path(task).parent() {
+            Some(parent) => fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+    }
+
+    /// Encrypts a task's plaintext note beside itself, shredding the
+    /// plaintext. Returns the new path of the note, if it was encrypted.
+    fn seal(&mut self, task: &Task) -> io::Result<Option<PathBuf>> {
+        let plaintext = match self.existing(task) {
+            Some(note) if !crypt::is_encrypted(note) => note.clone(),
+            _ => return Ok(None),
+        };
+        let sealed = crypt::encrypted_path(&plaintext);
+        write(self.opt, &sealed, &read(self.opt, &plaintext)?)?;
+        crypt::shred(&plaintext)?;
+        for key in [task.uuid.as_str(), short_uuid(&task.uuid)] {
+            if let Some(path) = self.notes.get_mut(key) {
+                path.clone_from(&sealed);
+            }
+        }
+        Ok(Some(sealed))
+    }
+
+    /// Removes a task's directory if it was left empty, so that tasks without a
+    /// note or attachments do not clutter the root directory.
+    pub(crate) fn tidy(&self, task: &Task) -> io::Result<()> {
+        let path = self.note_path(task);
+        if path.exists() {
+            return Ok(());
+        }
+        match path.parent() {
+            Some(parent) => self.remove_empty(parent),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes a directory, and then each of its parents, for as long as they
+    /// are empty. Stops at the root directory.
+    fn remove_empty(&self, dir: &Path) -> io::Result<()> {
+        let root = Path::new(&self.opt.root_dir);
+        let mut dir = Some(dir);
+        while let Some(current) = dir {
+            if current == root || !current.starts_with(root) {
+                break;
+            }
+            match fs::read_dir(current) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+                Err(e) => return Err(e),
+                Ok(mut entries) =>
+                    if entries.next().is_some() {
+                        break;
+                    } else {
+                        fs::remove_dir(current)?;
+                    },
+            }
+            dir = current.parent();
+        }
+        Ok(())
+    }
+
+    /// Renames a task's note after its description or project changed, when
+    /// using [Naming::Readable]. The note keeps its layout and encryption.
+    /// Returns the new path of the note, if it moved.
+    pub(crate) fn rename(&mut self, task: &Task) -> io::Result<Option<PathBuf>> {
+        if self.opt.naming != Naming::Readable {
+            return Ok(None);
+        }
+        self.relocate_dir(task)?;
+        let existing = match self.existing(task) {
+            Some(existing) => existing,
+            None => return Ok(None),
+        };
+        let layout = if in_directory(existing) {
+            Layout::Directory
+        } else {
+            Layout::Flat
+        };
+        let encrypted = crypt::is_encrypted(existing);
+        self.relocate(task, layout, encrypted)
+    }
+
+    /// Moves a task's note (along with its directory, in [Layout::Directory])
+    /// to where it belongs under the given layout and encryption, and the
+    /// selected naming scheme. Returns the new path of the note, if it moved.
+    pub(crate) fn relocate(
+        &mut self,
+        task: &Task,
+        layout: Layout,
+        encrypted: bool,
+    ) -> io::Result<Option<PathBuf>> {
+        let from = match self.existing(task) {
+            Some(from) => from.clone(),
+            None => return Ok(None),
+        };
+        let to = self.expected_path(task, layout, encrypted);
+        if from == to {
+            return Ok(None);
+        }
+        if to.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("a note already exists at '{}'", to.display()),
+            ));
+        }
+        let to_parent = to
+            .parent()
+            .map_or_else(|| PathBuf::from(&self.opt.root_dir), Path::to_path_buf);
+
+        let mut current = from.clone();
+        if in_directory(&from) {
+            let from_dir = from.parent().map(Path::to_path_buf).unwrap_or_default();
+            match layout {
+                // the whole directory moves, so that attachments come along
+                Layout::Directory =>
+                    if from_dir != to_parent {
+                        if let Some(parent) = to_parent.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::rename(&from_dir, &to_parent)?;
+                        if let Some(parent) = from_dir.parent() {
+                            self.remove_empty(parent)?;
+                        }
+                        current = to_parent.join(from.file_name().unwrap_or_default());
+                    },
+                Layout::Flat =>
+                    if !self.attachments(task)?.is_empty() {
+                        return Err(io::Error::other(format!(
+                            "'{}' has attachments, which need the `dir` layout",
+                            from_dir.display()
+                        )));
+                    },
+            }
+        }
+
+        fs::create_dir_all(&to_parent)?;
+        if crypt::is_encrypted(&current) == crypt::is_encrypted(&to) {
+            fs::rename(&current, &to)?;
+        } else {
+            // notes are only ever decrypted in memory while they're re-written
+            let contents = read(self.opt, &current)?;
+            write(self.opt, &to, &contents)?;
+            if crypt::is_encrypted(&current) {
+                fs::remove_file(&current)?;
+            } else {
+                crypt::shred(&current)?;
+            }
+        }
+        if let Some(parent) = current.parent() {
+            self.remove_empty(parent)?;
+        }
+
+        self.notes.remove(&task.uuid);
+        self.notes.remove(short_uuid(&task.uuid));
+        if layout == Layout::Directory {
+            // the note now lives in the directory of its attachments
+            self.dirs.remove(&task.uuid);
+            self.dirs.remove(short_uuid(&task.uuid));
+        }
+        self.notes.insert(self.key(task), to.clone());
+        Ok(Some(to))
+    }
+
+    /// Moves a task's directory which holds attachments but no note to where
+    /// it belongs under the selected naming scheme. Returns the new path of
+    /// the directory, if it moved.
+    pub(crate) fn relocate_dir(&mut self, task: &Task) -> io::Result<Option<PathBuf>> {
+        let from = match self.existing_dir(task) {
+            Some(from) => from.clone(),
+            None => return Ok(None),
+        };
+        let to = self.base(task);
+        if from == to {
+            return Ok(None);
+        }
+        if to.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("a directory already exists at '{}'", to.display()),
+            ));
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&from, &to)?;
+        if let Some(parent) = from.parent() {
+            self.remove_empty(parent)?;
+        }
+
+        self.dirs.remove(&task.uuid);
+        self.dirs.remove(short_uuid(&task.uuid));
+        self.dirs.insert(self.key(task), to.clone());
+        Ok(Some(to))
+    }
+}
+
+/// Whether a note is stored in its task's directory, as in
+/// [Layout::Directory].
+fn in_directory(note: &Path) -> bool {
+    note.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(&format!("{}.", NOTE_NAME)))
+}
+
+/// Finds the UUID (or short UUID) inside of the name of a note or a task
+/// directory.
+fn key_of(name: &str) -> Option<String> {
+    if is_uuid(name) {
+        return Some(name.to_string());
+    }
+    match name.rsplit_once("--") {
+        Some((_, short))
+            if short.len() == SHORT_UUID_LEN && short.chars().all(|c| c.is_ascii_hexdigit()) =>
+            Some(short.to_string()),
+        _ => None,
+    }
+}
+
+/// The prefix of a UUID used in readable names.
+pub(crate) fn short_uuid(uuid: &str) -> &str {
+    uuid.get(..SHORT_UUID_LEN).unwrap_or(uuid)
+}
+
+/// Determines whether a note's key (as returned by [Resolver::notes]) belongs
+/// to a task.
+pub(crate) fn key_matches(key: &str, uuid: &str) -> bool {
+    uuid.starts_with(key)
+}
+
+/// The readable name of a task's note or directory, e.g.
+/// `fix-login-bug--1a2b3c4d`.
+fn readable_name(description: &str, uuid: &str) -> String {
+    // slugs may hold multi-byte characters, so they're cut by character
+    let name = slug(description)
+        .chars()
+        .take(MAX_SLUG_LEN)
+        .collect::<String>();
+    format!("{}--{}", name.trim_end_matches('-'), short_uuid(uuid))
+}
+
+/// Turns text into a lowercase, dash separated name which is safe to use as a
+/// file name.
+fn slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Reads the contents of a note, treating a missing note as an empty one.
+/// Encrypted notes are decrypted in memory.
+pub(crate) fn read(opt: &Opt, path: &Path) -> io::Result<String> {
+    let contents = match fs::read(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(String::new()),
+        Err(e) => return Err(e),
+        Ok(contents) => contents,
+    };
+    let contents = if crypt::is_encrypted(path) {
+        crypt::decrypt(opt, path, &contents)?
+    } else {
+        contents
+    };
+    String::from_utf8(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes the contents of a note, encrypting it when its path says it should
+/// be encrypted.
+pub(crate) fn write(opt: &Opt, path: &Path, contents: &str) -> io::Result<()> {
+    let encrypted = crypt::is_encrypted(path);
+    let contents = if encrypted {
+        crypt::encrypt(opt, contents.as_bytes())?
+    } else {
+        contents.as_bytes().to_vec()
+    };
+
+    // the note is written beside its destination and then renamed over it, so
+    // that it's never left half written. temporary files are only readable by
+    // the current user, which is kept for encrypted notes
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut file = tempfile::Builder::new()
+        .prefix(".taskn-")
+        .tempfile_in(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if !encrypted {
+            let permissions = fs::metadata(path).map_or_else(
+                |_| fs::Permissions::from_mode(0o644),
+                |metadata| metadata.permissions(),
+            );
+            file.as_file().set_permissions(permissions)?;
+        }
+    }
+    file.write_all(&contents)?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Determines whether the note at a path has any content.
+pub(crate) fn has_content(opt: &Opt, path: &Path) -> io::Result<bool> {
+    // a lot of editors will keep an "empty" line at the top of a file, so a naive
+    // 'byte size == 0' check won't cut it.
+    //
+    // because we expect notes to be VERY small (on the order of KB at most), we can
+    // just scan to see if there's any non-whitespace.
+    //
+    // NOTE: if perf becomes an issue, this will become a good place to refactor
+    Ok(read(opt, path)?.chars().any(|c| !c.is_whitespace()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "1a2b3c4d-1111-2222-3333-444455556666";
+
+    fn task(description: &str, project: Option<&str>) -> Task {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "uuid": UUID,
+            "description": description,
+            "status": "pending",
+            "project": project,
+        }))
+        .unwrap()
+    }
+
+    fn touch(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Fix the login bug!"), "fix-the-login-bug");
+        assert_eq!(slug("  (draft) notes -- v2  "), "draft-notes-v2");
+        assert_eq!(slug("Café Ünïcode"), "café-ünïcode");
+        assert_eq!(slug("?!"), "");
+    }
+
+    #[test]
+    fn test_readable_name() {
+        assert_eq!(readable_name("Fix the login bug", UUID), "fix-the-login-bug--1a2b3c4d");
+        assert_eq!(readable_name("", UUID), "--1a2b3c4d");
+    }
+
+    #[test]
+    fn test_readable_name_truncates_by_character() {
+        let description = format!("{}é and more", "a".repeat(MAX_SLUG_LEN - 1));
+        let name = readable_name(&description, UUID);
+        assert_eq!(name, format!("{}é--1a2b3c4d", "a".repeat(MAX_SLUG_LEN - 1)));
+
+        // a dash left at the cut is dropped
+        let description = format!("{} word", "a".repeat(MAX_SLUG_LEN - 1));
+        let name = readable_name(&description, UUID);
+        assert_eq!(name, format!("{}--1a2b3c4d", "a".repeat(MAX_SLUG_LEN - 1)));
+    }
+
+    #[test]
+    fn test_key_of() {
+        assert_eq!(key_of(UUID), Some(UUID.to_string()));
+        assert_eq!(key_of("fix-the-login-bug--1a2b3c4d"), Some("1a2b3c4d".to_string()));
+        assert_eq!(key_of("--1a2b3c4d"), Some("1a2b3c4d".to_string()));
+        assert_eq!(key_of("fix-the-login-bug--1a2b3c4"), None);
+        assert_eq!(key_of("fix-the-login-bug--1a2b3c4z"), None);
+        assert_eq!(key_of("notes"), None);
+    }
+
+    #[test]
+    fn test_key_matches() {
+        assert!(key_matches(UUID, UUID));
+        assert!(key_matches("1a2b3c4d", UUID));
+        assert!(!key_matches("1a2b3c4e", UUID));
+    }
+
+    #[test]
+    fn test_scan() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        for path in [
+            format!("{}.md", UUID),
+            "enc--4d5e6f70.md.age".to_string(),
+            "work/fix-bug--2b3c4d5e/note.md".to_string(),
+            "work/fix-bug--2b3c4d5e/log.txt".to_string(),
+            "pics--3c4d5e6f/a.png".to_string(),
+            ".git/HEAD".to_string(),
+            "readme.txt".to_string(),
+        ] {
+            touch(&root.join(path), "");
+        }
+        let opt = Opt::for_tests(root);
+        let resolver = Resolver::new(&opt).unwrap();
+        assert_eq!(resolver.notes(), vec![
+            (UUID, root.join(format!("{}.md", UUID)).as_path()),
+            ("4d5e6f70", root.join("enc--4d5e6f70.md.age").as_path()),
+            ("2b3c4d5e", root.join("work/fix-bug--2b3c4d5e/note.md").as_path()),
+        ]);
+        assert_eq!(resolver.dirs(), vec![("3c4d5e6f", root.join("pics--3c4d5e6f").as_path())]);
+    }
+
+    #[test]
+    fn test_task_dir_and_attachments() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        touch(&root.join("report--1a2b3c4d/data.csv"), "");
+        let mut opt = Opt::for_tests(root);
+        let task = task("Write the report", None);
+        let resolver = Resolver::new(&opt).unwrap();
+        assert_eq!(resolver.task_dir(&task), Some(root.join("report--1a2b3c4d")));
+        assert_eq!(resolver.attachments(&task).unwrap(), vec![
+            root.join("report--1a2b3c4d/data.csv")
+        ]);
+
+        fs::remove_dir_all(root.join("report--1a2b3c4d")).unwrap();
+        let resolver = Resolver::new(&opt).unwrap();
+        assert_eq!(resolver.task_dir(&task), None);
+        opt.layout = Layout::Directory;
+        let resolver = Resolver::new(&opt).unwrap();
+        assert_eq!(resolver.task_dir(&task), Some(root.join(UUID)));
+        assert!(resolver.attachments(&task).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_relocate_into_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let flat = root.join(format!("{}.md", UUID));
+        touch(&flat, "hello");
+        let opt = Opt::for_tests(root);
+        let task = task("Write the report", None);
+        let mut resolver = Resolver::new(&opt).unwrap();
+
+        let note = root.join(UUID).join("note.md");
+        let moved = resolver.relocate(&task, Layout::Directory, false).unwrap();
+        assert_eq!(moved, Some(note.clone()));
+        assert_eq!(fs::read_to_string(&note).unwrap(), "hello");
+        assert!(!flat.exists());
+        assert_eq!(resolver.note_path(&task), note);
+        // the note is already where it belongs
+        assert_eq!(resolver.relocate(&task, Layout::Directory, false).unwrap(), None);
+        assert_eq!(Resolver::new(&opt).unwrap().note_path(&task), note);
+    }
+
+    #[test]
+    fn test_rename_moves_attachments_with_the_note() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        touch(&root.join("old-name--1a2b3c4d/note.md"), "hello");
+        touch(&root.join("old-name--1a2b3c4d/a.png"), "");
+        let mut opt = Opt::for_tests(root);
+        opt.layout = Layout::Directory;
+        opt.naming = Naming::Readable;
+        let task = task("Write the report", Some("work.api"));
+        let mut resolver = Resolver::new(&opt).unwrap();
+
+        let dir = root.join("work/api/write-the-report--1a2b3c4d");
+        assert_eq!(resolver.rename(&task).unwrap(), Some(dir.join("note.md")));
+        assert_eq!(fs::read_to_string(dir.join("note.md")).unwrap(), "hello");
+        assert!(dir.join("a.png").exists());
+        assert!(!root.join("old-name--1a2b3c4d").exists());
+
+        // attachments can't be kept beside a flat note
+        assert!(resolver.relocate(&task, Layout::Flat, false).is_err());
+        assert!(dir.join("note.md").exists());
+    }
+
+    #[test]
+    fn test_relocate_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        touch(&root.join("work/old-name--1a2b3c4d/a.png"), "");
+        let mut opt = Opt::for_tests(root);
+        opt.naming = Naming::Readable;
+        let task = task("Write the report", None);
+        let mut resolver = Resolver::new(&opt).unwrap();
+
+        let dir = root.join("write-the-report--1a2b3c4d");
+        assert_eq!(resolver.relocate_dir(&task).unwrap(), Some(dir.clone()));
+        assert!(dir.join("a.png").exists());
+        // the project's directory is removed once it's empty
+        assert!(!root.join("work").exists());
+        assert_eq!(resolver.task_dir(&task), Some(dir));
+        assert_eq!(resolver.relocate_dir(&task).unwrap(), None);
+    }
+
+    #[test]
+    fn test_prepare_dir_moves_a_flat_note() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let flat = root.join(format!("{}.md", UUID));
+        touch(&flat, "hello");
+        let mut opt = Opt::for_tests(root);
+        let task = task("Write the report", None);
+        assert_eq!(Resolver::new(&opt).unwrap().prepare_dir(&task).unwrap(), None);
+        assert!(flat.exists());
+
+        opt.layout = Layout::Directory;
+        let mut resolver = Resolver::new(&opt).unwrap();
+        let dir = root.join(UUID);
+        assert_eq!(resolver.prepare_dir(&task).unwrap(), Some(dir.clone()));
+        assert!(!flat.exists());
+        assert_eq!(resolver.note_path(&task), dir.join("note.md"));
+        assert_eq!(fs::read_to_string(dir.join("note.md")).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_prepare_encrypts_a_plaintext_note() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        let plaintext = root.join(format!("{}.md", UUID));
+        touch(&plaintext, "hello");
+        let opt = crypt::tests::encrypted_opt(root);
+        let task = task("Write the report", None);
+        let mut resolver = Resolver::new(&opt).unwrap();
+
+        resolver.prepare(&task).unwrap();
+        let sealed = crypt::encrypted_path(&plaintext);
+        assert!(!plaintext.exists());
+        assert_eq!(resolver.note_path(&task), sealed);
+        assert_ne!(fs::read(&sealed).unwrap(), b"hello");
+        assert_eq!(resolver.read(&task).unwrap(), "hello");
+    }
+}
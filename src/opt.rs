@@ -1,7 +1,10 @@
 use clap::{crate_description, crate_name, AppSettings, Clap};
 use std::env;
 
-use crate::commands::Command;
+use crate::{
+    commands::Command,
+    notes::{Layout, Naming},
+};
 
 #[derive(Debug, Clap)]
 #[clap(
@@ -27,6 +30,43 @@ struct ProtoOpt {
     #[clap(long, short = 'r', default_value = "~/.taskn", next_line_help = true)]
     root_dir: String,
 
+    /// How notes are laid out in the root directory: `flat` stores each note
+    /// as `<uuid>.<ext>`, `dir` stores it as `<uuid>/note.<ext>` alongside
+    /// the task's attachments.
+    #[clap(long, short = 'l', default_value = "flat", next_line_help = true)]
+    layout: Layout,
+
+    /// How notes are named: `uuid` names each note after its task's UUID,
+    /// `readable` names it after the task's description, inside of
+    /// directories for its project (e.g. `work/review-pr--1a2b3c4d.md`).
+    #[clap(long, default_value = "uuid", next_line_help = true)]
+    naming: Naming,
+
+    /// Keep the history of the notes in a git repository inside of the root
+    /// directory, committing after every edit.
+    #[clap(long, next_line_help = true)]
+    git: bool,
+
+    /// The git remote which `taskn sync` pushes notes to and pulls them from.
+    #[clap(long, next_line_help = true)]
+    git_remote: Option<String>,
+
+    /// Store notes encrypted with age. Notes are decrypted into a private
+    /// temporary file while they are being edited.
+    #[clap(long, next_line_help = true)]
+    encrypt: bool,
+
+    /// An age identity file used to encrypt notes. If unset, taskn keeps an
+    /// identity in the root directory protected by a passphrase, which is
+    /// read from `$TASKN_PASSPHRASE` or prompted for.
+    #[clap(long, next_line_help = true)]
+    identity: Option<String>,
+
+    /// The configuration file. Defaults to
+    /// `$XDG_CONFIG_HOME/taskn/config.toml`.
+    #[clap(long, next_line_help = true)]
+    config: Option<String>,
+
     /// Only workon tasks with the `taskn` tag (only works with interactive, for now)
     #[clap(short, long = "only")]
     only_taskn: bool,
@@ -37,6 +77,7 @@ struct ProtoOpt {
 
     /// Any remaining arguments are passed along to taskwarrior while selecting
     /// tasks.
+    #[clap(global = true)]
     args: Vec<String>,
 }
 
@@ -46,6 +87,13 @@ pub(crate) struct Opt {
     pub(crate) only_taskn:  bool,
     pub(crate) file_format: String,
     pub(crate) root_dir:    String,
+    pub(crate) layout:      Layout,
+    pub(crate) naming:      Naming,
+    pub(crate) git:         bool,
+    pub(crate) git_remote:  Option<String>,
+    pub(crate) encrypt:     bool,
+    pub(crate) identity:    Option<String>,
+    pub(crate) config:      Option<String>,
     pub(crate) command:     Command,
     pub(crate) args:        Vec<String>,
 }
@@ -74,6 +122,17 @@ impl Opt {
             only_taskn:  proto_opt.only_taskn,
             file_format: proto_opt.file_format,
             root_dir:    shellexpand::tilde(&proto_opt.root_dir).to_string(),
+            layout:      proto_opt.layout,
+            naming:      proto_opt.naming,
+            git:         proto_opt.git,
+            git_remote:  proto_opt.git_remote,
+            encrypt:     proto_opt.encrypt,
+            identity:    proto_opt
+                .identity
+                .map(|identity| shellexpand::tilde(&identity).to_string()),
+            config:      proto_opt
+                .config
+                .map(|config| shellexpand::tilde(&config).to_string()),
             command:     proto_opt.command.unwrap_or_default(),
             args:        proto_opt.args,
         }
@@ -83,3 +142,25 @@ impl Opt {
         Self::from_proto_opt(ProtoOpt::parse())
     }
 }
+
+#[cfg(test)]
+impl Opt {
+    /// The default options, with notes kept in a (temporary) root directory.
+    pub(crate) fn for_tests(root_dir: &std::path::Path) -> Self {
+        Opt {
+            editor:      "vi".to_string(),
+            only_taskn:  false,
+            file_format: "md".to_string(),
+            root_dir:    root_dir.to_string_lossy().to_string(),
+            layout:      Layout::Flat,
+            naming:      Naming::Uuid,
+            git:         false,
+            git_remote:  None,
+            encrypt:     false,
+            identity:    None,
+            config:      None,
+            command:     Command::default(),
+            args:        vec![],
+        }
+    }
+}
@@ -1,14 +1,15 @@
 #![allow(unused)]
 use std::{
-    fmt,
+    collections::BTreeMap,
+    env, fmt,
     fs::{File, OpenOptions},
     io::{self, BufRead, BufReader, Read, Write},
     path::PathBuf,
-    process::Command,
+    process::{Command, Output, Stdio},
     str,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{offset::Local, DateTime, NaiveDateTime, TimeZone};
 use colored::Colorize;
 use serde::{de, Deserialize};
@@ -29,7 +30,7 @@ pub(crate) enum Error {
     InvalidData(#[source] serde_json::Error),
 }
 
-use crate::opt::Opt;
+use crate::{links::is_uuid, notes::Resolver, opt::Opt};
 
 // use task_hookrs::{import::import, task::Task as TaskData};
 
@@ -39,16 +40,40 @@ pub(crate) struct Task {
     pub(crate) description:         String,
     pub(crate) uuid:                String,
     pub(crate) status:              String,
+    pub(crate) project:             Option<String>,
     pub(crate) estimate:            Option<String>,
     pub(crate) tags:                Option<Vec<String>>,
+    pub(crate) priority:            Option<String>,
+    pub(crate) due:                 Option<ParsableDateTime>,
     pub(crate) wait:                Option<ParsableDateTime>,
-    #[cfg(target = "macos")]
+    pub(crate) scheduled:           Option<ParsableDateTime>,
+    pub(crate) entry:               Option<ParsableDateTime>,
+    pub(crate) modified:            Option<ParsableDateTime>,
+    pub(crate) urgency:             Option<f64>,
+    pub(crate) annotations:         Option<Vec<Annotation>>,
+    #[cfg(target_os = "macos")]
     pub(crate) taskn_reminder_uuid: Option<String>,
+    /// Every other attribute, e.g. UDAs, kept so that they can be shown
+    #[serde(flatten)]
+    pub(crate) extra:               BTreeMap<String, serde_json::Value>,
+}
+
+/// A timestamped note added to a task with `task annotate`
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct Annotation {
+    pub(crate) entry:       ParsableDateTime,
+    pub(crate) description: String,
 }
 
 impl Task {
     /// Saves anything stored inside this Task to taskwarrior.
     pub(crate) fn save(&self) -> io::Result<()> {
+        self.save_with::<&str>(&[])
+    }
+
+    /// Saves the task like [Task::save], making extra modifications in the same
+    /// `task modify`, e.g. `estimate:3`.
+    pub(crate) fn save_with<S: AsRef<str>>(&self, modifications: &[S]) -> io::Result<()> {
         let mut command = Command::new("task");
 
         command
@@ -59,7 +84,23 @@ impl Task {
             .arg(&self.uuid)
             .arg("modify")
             .arg(&self.description)
-            .arg(format!("status:{}", self.status));
+            .arg(format!("status:{}", self.status))
+            .arg(format!("project:{}", self.project.as_deref().unwrap_or_default()))
+            .arg(format!("tags:{}", self.tags.as_deref().unwrap_or_default().join(",")))
+            .arg(format!("priority:{}", self.priority.as_deref().unwrap_or_default()));
+        for (name, date) in [
+            ("due", &self.due),
+            ("wait", &self.wait),
+            ("scheduled", &self.scheduled),
+        ] {
+            command.arg(format!(
+                "{}:{}",
+                name,
+                date.as_ref()
+                    .map(ParsableDateTime::to_taskwarrior)
+                    .unwrap_or_default()
+            ));
+        }
 
         // TODO: WTF is this for?
         // It just rewrites the name of every task
@@ -83,27 +124,25 @@ impl Task {
         //     command.arg("taskn_reminder_uuid:");
         // }
 
-        let _drop = command.output()?;
-        Ok(())
+        let output = command
+            .args(modifications.iter().map(AsRef::as_ref))
+            .output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "`task {} modify` failed: {}",
+                self.uuid,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
     }
 
     /// Loads the contents of the note associated with a particular Task. Note
-    /// that this requires the [Opt] parameter because it determines where
+    /// that this requires the [Resolver] parameter because it determines where
     /// the tasks are saved.
-    pub(crate) fn load_contents(&self, opt: &Opt) -> io::Result<String> {
-        let path = PathBuf::new()
-            .join(&opt.root_dir)
-            .join(&self.uuid)
-            .with_extension(&opt.file_format);
-        match File::open(path) {
-            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok("".to_string()),
-            Err(e) => Err(e),
-            Ok(mut file) => {
-                let mut buffer = String::new();
-                file.read_to_string(&mut buffer)?;
-                Ok(buffer)
-            },
-        }
+    pub(crate) fn load_contents(&self, resolver: &Resolver) -> io::Result<String> {
+        resolver.read(self)
     }
 
     #[allow(unused_lifetimes)]
@@ -138,6 +177,50 @@ impl Task {
         }
     }
 
+    /// Creates a task from the arguments to `task add`, i.e. a description
+    /// mixed with modifications like `+tag`, `project:` and `due:`.
+    pub(crate) fn add<S: AsRef<str>>(args: &[S]) -> Result<Self> {
+        let output = Command::new("task")
+            .arg("rc.confirmation=off")
+            .arg("rc.verbose=new-uuid")
+            .arg("add")
+            .args(args.iter().map(AsRef::as_ref))
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "`task add` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        // taskwarrior reports `Created task <uuid>.`
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let uuid = stdout
+            .split_whitespace()
+            .map(|word| word.trim_end_matches('.'))
+            .find(|word| is_uuid(word))
+            .ok_or_else(|| anyhow!("`task add` did not report the new task's UUID"))?;
+        Self::get_single(uuid)
+    }
+
+    /// Finds the one task matched by a filter.
+    pub(crate) fn get_single(filter: &str) -> Result<Self> {
+        let mut tasks = Self::get([filter].iter())?;
+        match tasks.len() {
+            1 => Ok(tasks.remove(0)),
+            0 => Err(anyhow!("no task matches '{}'", filter)),
+            n => Err(anyhow!("'{}' matches {} tasks, expected one", filter, n)),
+        }
+    }
+
+    /// The position of the task in the manual order, which is kept in its
+    /// `estimate`. Compared as a number, so that `10` comes after `9`.
+    pub(crate) fn manual_order(&self) -> Option<f64> {
+        self.estimate
+            .as_deref()
+            .and_then(|estimate| estimate.parse().ok())
+    }
+
     pub(crate) fn set_estimate(&mut self, estimate: Option<i32>) -> io::Result<()> {
         let estimate_arg;
         if let Some(estimate) = estimate {
@@ -151,6 +234,7 @@ impl Task {
             .arg("modify")
             .arg(estimate_arg)
             .output()?;
+        self.estimate = estimate.map(|estimate| estimate.to_string());
 
         Ok(())
     }
@@ -198,6 +282,30 @@ impl Task {
         }
     }
 
+    /// Adds an annotation to the task. All of the text is kept, even words
+    /// which look like modifications, e.g. `due:friday`.
+    pub(crate) fn annotate(&self, text: &str) -> Result<()> {
+        self.run_checked(&["annotate", "--", text])
+    }
+
+    /// Removes the annotation with the given text from the task.
+    pub(crate) fn denotate(&self, text: &str) -> Result<()> {
+        self.run_checked(&["denotate", "--", text])
+    }
+
+    fn run_checked(&self, args: &[&str]) -> Result<()> {
+        let output = run(&[self.uuid.as_str()], args)?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "`task {}` failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        }
+    }
+
     pub(crate) fn set_reminder_uuid(&mut self, uuid: &str) -> io::Result<()> {
         Command::new("task")
             .arg(&self.uuid)
@@ -209,9 +317,119 @@ impl Task {
     }
 }
 
+/// Lists the names of every project in use.
+pub(crate) fn projects() -> io::Result<Vec<String>> {
+    completion_list("_projects")
+}
+
+/// Lists the names of every tag in use.
+pub(crate) fn tags() -> io::Result<Vec<String>> {
+    completion_list("_tags")
+}
+
+/// Lists the names of every taskwarrior command, e.g. `modify` or `annotate`.
+pub(crate) fn commands() -> io::Result<Vec<String>> {
+    completion_list("_commands")
+}
+
+/// Lists the names of every attribute, including user defined ones.
+pub(crate) fn attributes() -> io::Result<Vec<String>> {
+    completion_list("_columns")
+}
+
+fn completion_list(command: &str) -> io::Result<Vec<String>> {
+    let output = Command::new("task")
+        .arg("rc.verbose=nothing")
+        .arg(command)
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Runs a taskwarrior command on the tasks with the given UUIDs, e.g.
+/// `modify +urgent`, without asking for confirmation.
+pub(crate) fn run<S: AsRef<str>>(uuids: &[S], args: &[S]) -> io::Result<Output> {
+    Command::new("task")
+        .arg("rc.confirmation=off")
+        .arg("rc.bulk=0")
+        .args(uuids.iter().map(AsRef::as_ref))
+        .args(args.iter().map(AsRef::as_ref))
+        .stdin(Stdio::null())
+        .output()
+}
+
+/// Counts the tasks matching a filter, using `task count`.
+pub(crate) fn count<S: AsRef<str>>(filter: &[S]) -> Result<usize> {
+    let output = Command::new("task")
+        .arg("rc.verbose=nothing")
+        .args(filter.iter().map(AsRef::as_ref))
+        .arg("count")
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("`task count` printed '{}' instead of a number", stdout.trim()))
+}
+
+/// Reads a taskwarrior configuration value, e.g. `report.next.columns`. An
+/// unset value is empty.
+pub(crate) fn config_value(name: &str) -> io::Result<String> {
+    let output = Command::new("task")
+        .arg("rc.verbose=nothing")
+        .arg("_get")
+        .arg(format!("rc.{}", name))
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The directory taskwarrior keeps its data in, from `$TASKDATA` or the
+/// `data.location` setting.
+pub(crate) fn data_location() -> io::Result<PathBuf> {
+    let location = match env::var("TASKDATA") {
+        Ok(location) => location,
+        Err(_) => config_value("data.location")?,
+    };
+    if location.is_empty() {
+        return Err(io::Error::other("data.location is not set"));
+    }
+    Ok(PathBuf::from(tilde(&location).to_string()))
+}
+
+/// Evaluates a taskwarrior date expression, e.g. `tomorrow`, `eow` or
+/// `2021-09-01`, using `task calc`.
+pub(crate) fn calc_date(expression: &str) -> Result<ParsableDateTime> {
+    let output = Command::new("task")
+        .arg("rc.verbose=nothing")
+        .arg("calc")
+        .arg(expression)
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // anything which isn't a date is echoed back as it is
+    NaiveDateTime::parse_from_str(stdout.trim(), ISO_FORMAT)
+        .ok()
+        .and_then(|naive_date_time| Local.from_local_datetime(&naive_date_time).single())
+        .map(ParsableDateTime)
+        .ok_or_else(|| anyhow!("'{}' is not a date", expression))
+}
+
+/// The format taskwarrior accepts and prints for dates in the local timezone
+const ISO_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub(crate) struct ParsableDateTime(pub(crate) DateTime<Local>);
 
+impl ParsableDateTime {
+    /// Formats the date so that it can be passed back to taskwarrior.
+    pub(crate) fn to_taskwarrior(&self) -> String {
+        self.0.format(ISO_FORMAT).to_string()
+    }
+}
+
 impl<'de> Deserialize<'de> for ParsableDateTime {
     fn deserialize<D: de::Deserializer<'de>>(
         deserializer: D,